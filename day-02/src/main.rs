@@ -1,36 +1,85 @@
 use clap::Parser;
 use std::{
-    fs::File,
+    collections::BTreeMap,
+    fs::{self, File},
     io::{self, BufRead, BufReader, Lines},
     path::{Path, PathBuf},
     process,
     str::FromStr,
 };
 
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{alpha1, char, digit1, multispace0, space0, space1},
+    combinator::{all_consuming, eof, map_res},
+    error::Error,
+    multi::separated_list0,
+    sequence::{delimited, pair, separated_pair, tuple},
+    Finish, IResult,
+};
 use thiserror::Error;
 
 #[derive(Parser)]
 struct Cli {
     input_file: PathBuf,
+    /// Bag limits in round format, ex: "12 red, 13 green, 14 blue"
+    #[arg(long, conflicts_with = "bag_file")]
+    bag: Option<String>,
+    /// File containing bag limits in round format, one or more colors per line
+    #[arg(long)]
+    bag_file: Option<PathBuf>,
+    /// Report the smallest bag that makes every game possible, or only the listed game ids
+    #[arg(long, value_delimiter = ',', num_args = 0..)]
+    min_bag: Option<Vec<usize>>,
+    /// List the games that become possible if a color's limit is raised, ex: "red=2"
+    #[arg(long, value_parser = parse_raise)]
+    raise: Option<(String, usize)>,
 }
 
-const MAX_RED: usize = 12;
-const MAX_GREEN: usize = 13;
-const MAX_BLUE: usize = 14;
+/// The bag from the puzzle description
+const DEFAULT_BAG: &str = "12 red, 13 green, 14 blue";
+
+/// A number of cubes for each color
+type CubeSet = BTreeMap<String, usize>;
 
 fn main() {
     // Get command line arguments
     let args = Cli::parse();
 
+    // Load the bag limits from the CLI, a config file or the default
+    let bag = match load_bag(&args) {
+        Ok(bag) => bag,
+        Err(e) => {
+            eprintln!("Could not load bag limits: {}", e);
+            process::exit(1);
+        }
+    };
+
     // Read file from CLI arg
     if let Ok(lines) = read_lines(&args.input_file) {
-        let (id_sum, max_power) = lines
+        let mut games: Vec<Game> = vec![];
+        for (line_num, line) in lines.map_while(Result::ok).enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
             // convert to game
-            .flat_map(|line| Game::from_str(line.unwrap().as_str()))
+            match Game::from_str(&line) {
+                Ok(game) => games.push(game),
+                Err(e) => {
+                    eprintln!("Line {}: {}", line_num + 1, e);
+                    process::exit(1);
+                }
+            }
+        }
+
+        let (id_sum, max_power) = games
+            .iter()
             // fold into two values: (sum of ids, sum of game power)
             .fold((0, 0), |mut acc, game| {
                 // check if game is above limit
-                if !game.above_limit((MAX_RED, MAX_GREEN, MAX_BLUE)) {
+                if !game.above_limit(&bag) {
                     // add game id if not above limit
                     acc.0 += game.id;
                 }
@@ -45,93 +94,242 @@ fn main() {
             "Part 1 Game Sum: {}\nPart 2 Max Power: {}",
             id_sum, max_power
         );
+
+        if let Some(ids) = &args.min_bag {
+            let subset: Vec<&Game> = games
+                .iter()
+                .filter(|game| ids.is_empty() || ids.contains(&game.id))
+                .collect();
+            let bag = min_bag(subset);
+            println!(
+                "Minimum Bag: {} ({} cubes)",
+                format_cubes(&bag),
+                bag.values().sum::<usize>()
+            );
+        }
+
+        if let Some((color, amount)) = &args.raise {
+            let flipped: Vec<String> = games_unlocked_by_raise(&games, &bag, color, *amount)
+                .iter()
+                .map(|game| game.id.to_string())
+                .collect();
+            println!(
+                "Games possible with {} more {}: [{}]",
+                amount,
+                color,
+                flipped.join(", ")
+            );
+        }
     } else {
         eprintln!("Could not read file: {}", args.input_file.display());
         process::exit(1);
     }
 }
 
-#[derive(Error, Debug)]
+/// Load the bag limits from the CLI arguments
+///
+/// A `--bag` value takes the form of a round. A `--bag-file` is the same
+/// format, but each line may hold one or more colors. If neither is given,
+/// the bag from the puzzle description is used.
+///
+/// Arguments:
+/// - args: The parsed command line arguments
+fn load_bag(args: &Cli) -> Result<CubeSet, String> {
+    let bag_str = if let Some(bag) = &args.bag {
+        bag.clone()
+    } else if let Some(bag_file) = &args.bag_file {
+        fs::read_to_string(bag_file)
+            .map_err(|e| format!("{}: {}", bag_file.display(), e))?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ")
+    } else {
+        String::from(DEFAULT_BAG)
+    };
+
+    GameRound::from_str(&bag_str)
+        .map(|round| round.cubes)
+        .map_err(|e| e.to_string())
+}
+
+/// Parse a `--raise` value in the form of "color=amount"
+///
+/// Arguments:
+/// - s: The raw CLI value
+fn parse_raise(s: &str) -> Result<(String, usize), String> {
+    let (color, amount) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Expected color=amount, got: {}", s))?;
+    let amount = amount
+        .trim()
+        .parse::<usize>()
+        .map_err(|e| format!("Invalid amount {}: {}", amount, e))?;
+
+    Ok((String::from(color.trim()), amount))
+}
+
+/// Format a set of cubes in round format, ex: "12 red, 13 green"
+///
+/// Arguments:
+/// - cubes: The cubes to format
+fn format_cubes(cubes: &CubeSet) -> String {
+    cubes
+        .iter()
+        .map(|(color, count)| format!("{} {}", count, color))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Find the smallest bag that is consistent with every round of every game
+///
+/// Any bag holding at least this many cubes of each color (and any number of
+/// other colors) makes all of the games possible, so this is also the lower
+/// bound of every bag configuration compatible with the games.
+///
+/// Arguments:
+/// - games: The games that must be possible
+fn min_bag<'a>(games: impl IntoIterator<Item = &'a Game>) -> CubeSet {
+    let mut bag = CubeSet::new();
+
+    for game in games {
+        for (color, count) in game.max_cubes() {
+            let max = bag.entry(color).or_default();
+            if count > *max {
+                *max = count;
+            }
+        }
+    }
+
+    bag
+}
+
+/// Find the games that are impossible with a bag, but become possible if one
+/// color's limit is raised
+///
+/// Arguments:
+/// - games: The games to check
+/// - bag: The current bag limits
+/// - color: The color to raise
+/// - amount: How many cubes of that color to add to the bag
+fn games_unlocked_by_raise<'a>(
+    games: &'a [Game],
+    bag: &CubeSet,
+    color: &str,
+    amount: usize,
+) -> Vec<&'a Game> {
+    let mut raised = bag.clone();
+    *raised.entry(String::from(color)).or_default() += amount;
+
+    games
+        .iter()
+        .filter(|game| game.above_limit(bag) && !game.above_limit(&raised))
+        .collect()
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
 enum GameParseError {
-    #[error("Unknown game format: {0}")]
-    UnknownGameFormat(String),
-    #[error("Unable to find Game Id: {0}")]
-    MissingGameId(String),
-    #[error("Unable to parse Game Id: {0}")]
-    ParseGameId(String),
-    #[error("Unable to find any rounds in Game Id: {0}")]
-    MissingRounds(String),
-    #[error("Unknown color amount format: {0}")]
-    UnknownColorAmount(String),
-    #[error("Unknown color: {0}")]
-    UnknownColor(String),
-    #[error("Unknown round format: {0}")]
-    UnknownRoundFormat(String),
+    #[error("Unknown game format at column {column}: {input}")]
+    UnknownGameFormat { column: usize, input: String },
+    #[error("Unable to find any rounds in Game {0}")]
+    MissingRounds(usize),
+    #[error("Unknown round format at column {column}: {input}")]
+    UnknownRoundFormat { column: usize, input: String },
+    #[error(
+        "Unknown round format in Game {game_id}, round index {round} at column {column}: {input}"
+    )]
+    InvalidRound {
+        game_id: usize,
+        round: usize,
+        column: usize,
+        input: String,
+    },
+}
+
+/// Find the 1-based column where the remaining input starts within the full input
+///
+/// Arguments:
+/// - full: The full input that was parsed
+/// - remaining: The part of the input the parser stopped at
+fn column(full: &str, remaining: &str) -> usize {
+    full.len() - remaining.len() + 1
+}
+
+/// Parse an amount of cubes and their color
+///
+/// Example: "3  blue" -> (3, "blue")
+fn parse_cube_count(s: &str) -> IResult<&str, (usize, &str)> {
+    separated_pair(map_res(digit1, str::parse::<usize>), space1, alpha1)(s)
+}
+
+/// Parse a comma separated list of cube counts, which may be empty
+///
+/// Example: " 3 blue ,4 red " -> [(3, "blue"), (4, "red")]
+fn parse_round(s: &str) -> IResult<&str, Vec<(usize, &str)>> {
+    delimited(
+        space0,
+        separated_list0(delimited(space0, char(','), space0), parse_cube_count),
+        multispace0,
+    )(s)
+}
+
+/// Parse the game header, returning the game id
+///
+/// Example: "Game 12:" -> 12
+fn parse_game_id(s: &str) -> IResult<&str, usize> {
+    delimited(
+        tuple((space0, tag("Game"), space1)),
+        map_res(digit1, str::parse::<usize>),
+        pair(space0, char(':')),
+    )(s)
 }
 
 #[derive(Debug, Default, PartialEq, Eq)]
 struct GameRound {
-    pub red: usize,
-    pub green: usize,
-    pub blue: usize,
+    pub cubes: CubeSet,
+}
+
+impl<'a> FromIterator<(usize, &'a str)> for GameRound {
+    /// Build a round from cube counts, adding up any repeated colors
+    fn from_iter<T: IntoIterator<Item = (usize, &'a str)>>(iter: T) -> Self {
+        let mut round = GameRound::default();
+
+        for (count, color) in iter {
+            *round.cubes.entry(String::from(color)).or_default() += count;
+        }
+
+        round
+    }
 }
 
 impl FromStr for GameRound {
     type Err = GameParseError;
 
     /// Parses a round from a comma separated list of colors drawn
-    /// If string is empty, it will return a default value of all zeros
+    /// If string is empty, it will return a round with no cubes
     ///
     /// Example:
-    /// "3 blue, 4 red, 2 green" -> GameRound { red: 4, green: 2, blue: 3 }
-    /// "" -> GameRound { red: 0, green: 0, blue: 0 }
+    /// "3 blue, 4 red, 2 green" -> GameRound { cubes: { blue: 3, green: 2, red: 4 } }
+    /// "" -> GameRound { cubes: {} }
     ///
     /// Arguments:
     /// - s: A string representing a comma separated list of colors drawn
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut round = GameRound::default();
-
-        // if s is empty, return the default value
-        if s.is_empty() {
-            return Ok(round);
-        }
-
-        // split on comma to get each color
-        for color_group in s.split(',') {
-            // color_group should not me "X color"
-            let mut values = color_group.trim().split(' ');
-            if values.clone().count() != 2 {
-                return Err(Self::Err::UnknownRoundFormat(String::from(color_group)));
-            }
-            // first value should be a number
-            // we know it's there from the count() check above
-            let num_str = values.next().unwrap();
-            // last value should be the color
-            // we know it's there from the count() check above
-            let color = values.next().unwrap();
-
-            if let Ok(num) = num_str.parse::<usize>() {
-                match color {
-                    "red" => round.red += num,
-                    "green" => round.green += num,
-                    "blue" => round.blue += num,
-                    _ => return Err(Self::Err::UnknownColor(String::from(color))),
-                };
-            } else {
-                return Err(Self::Err::UnknownColorAmount(String::from(num_str)));
-            }
+        match all_consuming(parse_round)(s).finish() {
+            Ok((_remaining, counts)) => Ok(counts.into_iter().collect()),
+            Err(Error { input, .. }) => Err(Self::Err::UnknownRoundFormat {
+                column: column(s, input),
+                input: String::from(s),
+            }),
         }
-
-        Ok(round)
     }
 }
 
 #[derive(Debug, Default, PartialEq, Eq)]
 struct Game {
     pub id: usize,
-    pub red_total: usize,
-    pub green_total: usize,
-    pub blue_total: usize,
+    pub totals: CubeSet,
     pub rounds: Vec<GameRound>,
 }
 
@@ -141,9 +339,9 @@ impl Game {
     /// Arguments:
     /// - round: A game round to add to the game
     pub fn add_round(&mut self, round: GameRound) -> &mut Self {
-        self.red_total += round.red;
-        self.green_total += round.green;
-        self.blue_total += round.blue;
+        for (color, count) in &round.cubes {
+            *self.totals.entry(color.clone()).or_default() += count;
+        }
         self.rounds.push(round);
 
         self
@@ -151,21 +349,16 @@ impl Game {
 
     /// Determine the max number of cubes needed for each color
     ///
-    /// Returns a tuple representing (red, green, blue)
-    pub fn max_cubes(&self) -> (usize, usize, usize) {
-        let mut max_cubes = (0, 0, 0);
+    /// Returns the max count for each color seen in any round
+    pub fn max_cubes(&self) -> CubeSet {
+        let mut max_cubes = CubeSet::new();
 
         for round in &self.rounds {
-            if round.red > max_cubes.0 {
-                max_cubes.0 = round.red;
-            }
-
-            if round.green > max_cubes.1 {
-                max_cubes.1 = round.green;
-            }
-
-            if round.blue > max_cubes.2 {
-                max_cubes.2 = round.blue;
+            for (color, &count) in &round.cubes {
+                let max = max_cubes.entry(color.clone()).or_default();
+                if count > *max {
+                    *max = count;
+                }
             }
         }
 
@@ -174,43 +367,29 @@ impl Game {
 
     /// Calculate the "cube power"
     ///
-    /// The power of a set of cubes is equal to the numbers of red, green, and
-    /// blue cubes multiplied together.
+    /// The power of a set of cubes is equal to the numbers of cubes of each
+    /// color multiplied together.
     pub fn cube_power(&self) -> usize {
-        let max_cubes = self.max_cubes();
-        let mut cube_power = 1;
-
-        // Red
-        if max_cubes.0 > 0 {
-            cube_power *= max_cubes.0;
-        }
-
-        // Green
-        if max_cubes.1 > 0 {
-            cube_power *= max_cubes.1;
-        }
-
-        // Blue
-        if max_cubes.2 > 0 {
-            cube_power *= max_cubes.2;
-        }
-
-        cube_power
+        self.max_cubes()
+            .values()
+            .filter(|&&count| count > 0)
+            .product()
     }
 
     /// Check is the game requires more cubes than a limit
     ///
+    /// A color missing from the limit is treated as having a max of zero
+    ///
     /// Arguments:
-    /// - limit: A tuple of (red, green, blue) values that represent the max for that color
-    pub fn above_limit(&self, limit: (usize, usize, usize)) -> bool {
+    /// - limit: The max number of cubes allowed for each color
+    pub fn above_limit(&self, limit: &CubeSet) -> bool {
         // Check each round, making sure not to go over the max allowed for each color
-        for round in &self.rounds {
-            if round.red > limit.0 || round.green > limit.1 || round.blue > limit.2 {
-                return true;
-            }
-        }
-
-        false
+        self.rounds.iter().any(|round| {
+            round
+                .cubes
+                .iter()
+                .any(|(color, &count)| count > limit.get(color).copied().unwrap_or(0))
+        })
     }
 }
 
@@ -223,52 +402,51 @@ impl FromStr for Game {
     /// "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green" ->
     ///  Game {
     ///     id: 1
-    ///     red_total: 5,
-    ///     green_total: 4,
-    ///     blue_total: 9,
+    ///     totals: { blue: 9, green: 4, red: 5 },
     ///     rounds: [
-    ///         GameRound { red: 4, green: 0, blue: 3 },
-    ///         GameRound { red: 1, green: 2, blue: 6 },
-    ///         GameRound { red: 0, green: 2, blue: 0 },
+    ///         GameRound { cubes: { blue: 3, red: 4 } },
+    ///         GameRound { cubes: { blue: 6, green: 2, red: 1 } },
+    ///         GameRound { cubes: { green: 2 } },
     ///     ]
     ///  }
     ///
     /// Arguments:
     /// - s: A string representing a game
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut game = Game::default();
-
-        // split on : to get the game info and the rounds
-        let mut colon_split = s.split(':');
-        let game_info = colon_split.next();
-        let round_info = colon_split.next();
-
-        if game_info.is_none() || round_info.is_none() {
-            return Err(Self::Err::UnknownGameFormat(String::from(s)));
-        }
-
-        // parse game info
-        let mut game_split = game_info.unwrap().trim().split(' ');
-        // skip the 0th element as it should just be "Game"
-        let game_id = game_split.nth(1);
-        if game_id.is_none() {
-            return Err(Self::Err::MissingGameId(String::from(game_info.unwrap())));
-        }
-
-        if let Ok(game_id_num) = game_id.unwrap().parse::<usize>() {
-            game.id = game_id_num;
-        } else {
-            return Err(Self::Err::ParseGameId(String::from(game_id.unwrap())));
-        }
+        let (mut rest, id) = parse_game_id(s).finish().map_err(|Error { input, .. }| {
+            Self::Err::UnknownGameFormat {
+                column: column(s, input),
+                input: String::from(s),
+            }
+        })?;
 
-        if round_info.unwrap().is_empty() {
-            return Err(Self::Err::MissingRounds(game.id.to_string()));
+        if rest.trim().is_empty() {
+            return Err(Self::Err::MissingRounds(id));
         }
 
-        // split rounds on ; to get each individual round
-        for round in round_info.unwrap().trim().split(';') {
-            // parse rounds and add colors
-            game.add_round(GameRound::from_str(round)?);
+        let mut game = Game {
+            id,
+            ..Default::default()
+        };
+
+        // rounds are separated by ; and run until the end of the line
+        loop {
+            let (remaining, (counts, separator)) = pair(parse_round, alt((tag(";"), eof)))(rest)
+                .finish()
+                .map_err(|Error { input, .. }| Self::Err::InvalidRound {
+                    game_id: id,
+                    round: game.rounds.len(),
+                    column: column(s, input),
+                    input: String::from(s),
+                })?;
+
+            game.add_round(counts.into_iter().collect());
+
+            // eof matches an empty separator
+            if separator.is_empty() {
+                break;
+            }
+            rest = remaining;
         }
 
         Ok(game)
@@ -287,45 +465,50 @@ where
 
 #[cfg(test)]
 mod tests_day_02 {
-    use super::{Game, GameRound, MAX_BLUE, MAX_GREEN, MAX_RED};
+    use super::{
+        games_unlocked_by_raise, min_bag, CubeSet, Game, GameParseError, GameRound, DEFAULT_BAG,
+    };
     use std::str::FromStr;
 
+    fn cube_set(cubes: &[(&str, usize)]) -> CubeSet {
+        cubes
+            .iter()
+            .map(|&(color, count)| (String::from(color), count))
+            .collect()
+    }
+
+    fn round(cubes: &[(&str, usize)]) -> GameRound {
+        GameRound {
+            cubes: cube_set(cubes),
+        }
+    }
+
+    fn default_bag() -> CubeSet {
+        GameRound::from_str(DEFAULT_BAG).unwrap().cubes
+    }
+
     #[test]
     fn parse_game_round_from_str() {
         assert_eq!(
             GameRound::from_str("3 blue, 4 red").unwrap(),
-            GameRound {
-                red: 4,
-                green: 0,
-                blue: 3
-            }
+            round(&[("red", 4), ("blue", 3)])
         );
 
         assert_eq!(
             GameRound::from_str("1 red, 2 green, 6 blue").unwrap(),
-            GameRound {
-                red: 1,
-                green: 2,
-                blue: 6
-            }
+            round(&[("red", 1), ("green", 2), ("blue", 6)])
         );
 
         assert_eq!(
             GameRound::from_str("2 green").unwrap(),
-            GameRound {
-                red: 0,
-                green: 2,
-                blue: 0
-            }
+            round(&[("green", 2)])
         );
 
+        assert_eq!(GameRound::from_str("").unwrap(), round(&[]));
+
         assert_eq!(
-            GameRound::from_str("").unwrap(),
-            GameRound {
-                red: 0,
-                green: 0,
-                blue: 0
-            }
+            GameRound::from_str("2 green, 5 purple").unwrap(),
+            round(&[("green", 2), ("purple", 5)])
         );
     }
 
@@ -335,56 +518,112 @@ mod tests_day_02 {
             Game::from_str("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green").unwrap(),
             Game {
                 id: 1,
-                red_total: 5,
-                green_total: 4,
-                blue_total: 9,
+                totals: cube_set(&[("red", 5), ("green", 4), ("blue", 9)]),
                 rounds: vec![
-                    GameRound {
-                        red: 4,
-                        green: 0,
-                        blue: 3
-                    },
-                    GameRound {
-                        red: 1,
-                        green: 2,
-                        blue: 6
-                    },
-                    GameRound {
-                        red: 0,
-                        green: 2,
-                        blue: 0
-                    },
+                    round(&[("red", 4), ("blue", 3)]),
+                    round(&[("red", 1), ("green", 2), ("blue", 6)]),
+                    round(&[("green", 2)]),
                 ]
             }
         )
     }
 
+    #[test]
+    fn parse_with_extra_whitespace() {
+        assert_eq!(
+            GameRound::from_str(" 3  blue ,4 red ").unwrap(),
+            round(&[("red", 4), ("blue", 3)])
+        );
+
+        assert_eq!(
+            Game::from_str("Game  7 :3  blue;  2 green ; 1\tred").unwrap(),
+            Game {
+                id: 7,
+                totals: cube_set(&[("red", 1), ("green", 2), ("blue", 3)]),
+                rounds: vec![
+                    round(&[("blue", 3)]),
+                    round(&[("green", 2)]),
+                    round(&[("red", 1)]),
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            GameRound::from_str("3 blue, red").unwrap_err(),
+            GameParseError::UnknownRoundFormat {
+                column: 7,
+                input: String::from("3 blue, red")
+            }
+        );
+
+        assert_eq!(
+            Game::from_str("Gam 1: 3 blue").unwrap_err(),
+            GameParseError::UnknownGameFormat {
+                column: 1,
+                input: String::from("Gam 1: 3 blue")
+            }
+        );
+
+        assert_eq!(
+            Game::from_str("Game 1:").unwrap_err(),
+            GameParseError::MissingRounds(1)
+        );
+
+        assert_eq!(
+            Game::from_str("Game 4: 3 blue; 2 green, 1; 4 red").unwrap_err(),
+            GameParseError::InvalidRound {
+                game_id: 4,
+                round: 1,
+                column: 24,
+                input: String::from("Game 4: 3 blue; 2 green, 1; 4 red")
+            }
+        );
+    }
+
     #[test]
     fn game_max_cubes() {
         let game_1 =
             Game::from_str("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green").unwrap();
-        assert_eq!(game_1.max_cubes(), (4, 2, 6));
+        assert_eq!(
+            game_1.max_cubes(),
+            cube_set(&[("red", 4), ("green", 2), ("blue", 6)])
+        );
 
         let game_2 =
             Game::from_str("Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue")
                 .unwrap();
-        assert_eq!(game_2.max_cubes(), (1, 3, 4));
+        assert_eq!(
+            game_2.max_cubes(),
+            cube_set(&[("red", 1), ("green", 3), ("blue", 4)])
+        );
 
         let game_3 = Game::from_str(
             "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red",
         )
         .unwrap();
-        assert_eq!(game_3.max_cubes(), (20, 13, 6));
+        assert_eq!(
+            game_3.max_cubes(),
+            cube_set(&[("red", 20), ("green", 13), ("blue", 6)])
+        );
 
         let game_4 = Game::from_str(
             "Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red",
         )
         .unwrap();
-        assert_eq!(game_4.max_cubes(), (14, 3, 15));
+        assert_eq!(
+            game_4.max_cubes(),
+            cube_set(&[("red", 14), ("green", 3), ("blue", 15)])
+        );
 
         let game_5 =
             Game::from_str("Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green").unwrap();
-        assert_eq!(game_5.max_cubes(), (6, 3, 2));
+        assert_eq!(
+            game_5.max_cubes(),
+            cube_set(&[("red", 6), ("green", 3), ("blue", 2)])
+        );
     }
 
     #[test]
@@ -413,33 +652,104 @@ mod tests_day_02 {
         let game_5 =
             Game::from_str("Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green").unwrap();
         assert_eq!(game_5.cube_power(), 36);
+
+        let game_6 = Game::from_str("Game 6: 2 red, 3 purple; 5 orange, 1 purple").unwrap();
+        assert_eq!(game_6.cube_power(), 30);
     }
 
     #[test]
     fn game_above_limit() {
+        let bag = default_bag();
+
         let game_1 =
             Game::from_str("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green").unwrap();
-        assert!(!game_1.above_limit((MAX_RED, MAX_GREEN, MAX_BLUE)));
+        assert!(!game_1.above_limit(&bag));
 
         let game_2 =
             Game::from_str("Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue")
                 .unwrap();
-        assert!(!game_2.above_limit((MAX_RED, MAX_GREEN, MAX_BLUE)));
+        assert!(!game_2.above_limit(&bag));
 
         let game_3 = Game::from_str(
             "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red",
         )
         .unwrap();
-        assert!(game_3.above_limit((MAX_RED, MAX_GREEN, MAX_BLUE)));
+        assert!(game_3.above_limit(&bag));
 
         let game_4 = Game::from_str(
             "Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red",
         )
         .unwrap();
-        assert!(game_4.above_limit((MAX_RED, MAX_GREEN, MAX_BLUE)));
+        assert!(game_4.above_limit(&bag));
 
         let game_5 =
             Game::from_str("Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green").unwrap();
-        assert!(!game_5.above_limit((MAX_RED, MAX_GREEN, MAX_BLUE)));
+        assert!(!game_5.above_limit(&bag));
+    }
+
+    #[test]
+    fn game_above_custom_limit() {
+        let bag = cube_set(&[("red", 2), ("purple", 3)]);
+
+        let game_1 = Game::from_str("Game 1: 2 red, 3 purple; 1 purple").unwrap();
+        assert!(!game_1.above_limit(&bag));
+
+        let game_2 = Game::from_str("Game 2: 2 red, 4 purple").unwrap();
+        assert!(game_2.above_limit(&bag));
+
+        // colors missing from the bag can't be drawn
+        let game_3 = Game::from_str("Game 3: 1 red; 1 orange").unwrap();
+        assert!(game_3.above_limit(&bag));
+    }
+
+    fn example_games() -> Vec<Game> {
+        [
+            "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green",
+            "Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue",
+            "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red",
+            "Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red",
+            "Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green",
+        ]
+        .iter()
+        .map(|line| Game::from_str(line).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn games_min_bag() {
+        let games = example_games();
+
+        let bag = min_bag(&games);
+        assert_eq!(bag, cube_set(&[("red", 20), ("green", 13), ("blue", 15)]));
+        assert!(games.iter().all(|game| !game.above_limit(&bag)));
+
+        let subset: Vec<&Game> = games.iter().filter(|game| game.id != 3).collect();
+        let bag = min_bag(subset);
+        assert_eq!(bag, cube_set(&[("red", 14), ("green", 3), ("blue", 15)]));
+        assert_eq!(bag.values().sum::<usize>(), 32);
+
+        assert_eq!(min_bag(&[]), CubeSet::new());
+    }
+
+    #[test]
+    fn games_unlocked_by_raising_a_color() {
+        let games = example_games();
+        let bag = default_bag();
+
+        let ids = |unlocked: Vec<&Game>| unlocked.iter().map(|game| game.id).collect::<Vec<_>>();
+
+        assert_eq!(
+            ids(games_unlocked_by_raise(&games, &bag, "red", 8)),
+            vec![3]
+        );
+        assert!(games_unlocked_by_raise(&games, &bag, "red", 7).is_empty());
+        // game 4 is above the limit for both red and blue
+        assert!(games_unlocked_by_raise(&games, &bag, "blue", 1).is_empty());
+
+        let bag = cube_set(&[("red", 14), ("green", 13), ("blue", 14)]);
+        assert_eq!(
+            ids(games_unlocked_by_raise(&games, &bag, "blue", 1)),
+            vec![4]
+        );
     }
 }
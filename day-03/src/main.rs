@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use std::{
     collections::{BTreeMap, BTreeSet},
     fmt::Debug,
@@ -12,6 +12,34 @@ use std::{
 #[derive(Parser)]
 struct Cli {
     input_file: PathBuf,
+    /// Sum the ratios of a symbol with an exact number of parts, ex: "#=3"
+    #[arg(long, value_parser = parse_junction)]
+    junction: Option<(char, usize)>,
+    /// Print the part numbers attached to each symbol and the unattached numbers
+    #[arg(long)]
+    report: bool,
+    /// Print the schematic, highlighting the counted part numbers
+    #[arg(long)]
+    render: Option<RenderStyle>,
+}
+
+/// Parse a `--junction` value in the form of "symbol=count"
+///
+/// Arguments:
+/// - s: The raw CLI value
+fn parse_junction(s: &str) -> Result<(char, usize), String> {
+    let (symbol, count) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Expected symbol=count, got: {}", s))?;
+    let mut symbol_chars = symbol.chars();
+    let (Some(symbol), None) = (symbol_chars.next(), symbol_chars.next()) else {
+        return Err(format!("Expected a single symbol, got: {}", symbol));
+    };
+    let count = count
+        .parse::<usize>()
+        .map_err(|e| format!("Invalid count {}: {}", count, e))?;
+
+    Ok((symbol, count))
 }
 
 fn main() {
@@ -21,99 +49,119 @@ fn main() {
     // Read file from CLI arg
     if let Ok(file) = read_to_string(&args.input_file) {
         let items = ItemMaps::from_map(file);
+
+        if let Some(style) = args.render {
+            print!("{}", items.render(style));
+        }
+
         let part_1_answer = items.get_part_numbers().iter().sum::<usize>();
         let part_2_answer = items.get_gear_ratios().iter().sum::<usize>();
 
         println!("Part 1: {}\nPart 2: {}", part_1_answer, part_2_answer);
+
+        if let Some((symbol, count)) = args.junction {
+            let junction_sum = items
+                .query_symbols(|s| s == symbol, |c| c == count)
+                .iter()
+                .map(SymbolMatch::ratio)
+                .sum::<usize>();
+            println!("Junction {}={}: {}", symbol, count, junction_sum);
+        }
+
+        if args.report {
+            let values = |numbers: &[PartNumber]| {
+                numbers
+                    .iter()
+                    .map(|number| number.value.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+
+            for (symbol, numbers) in items.group_parts_by_symbol() {
+                println!("Symbol {}: [{}]", symbol, values(&numbers));
+            }
+            println!("Unattached: [{}]", values(&items.get_unattached_numbers()));
+        }
     } else {
         eprintln!("Could not read file: {}", args.input_file.display());
         process::exit(1);
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 struct ItemPoint {
-    pub column: i32,
-    pub row: i32,
+    pub row: usize,
+    pub column: usize,
 }
 
-impl ItemPoint {
-    /// Get all the surrounding points
-    ///
-    /// -1 -1, -1 0, -1 1
-    ///  0 -1,  0 0,  0 1
-    ///  1 -1,  1 0,  1 1
-    pub fn get_surrounding_points(&self) -> Vec<ItemPoint> {
-        let mut points = Vec::new();
-
-        let offsets = [
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            (0, 1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-        ];
-
-        for offset in offsets {
-            points.push(ItemPoint {
-                column: self.column + offset.1,
-                row: self.row + offset.0,
-            });
-        }
-
-        points
-    }
+/// A number on the map
+///
+/// Two numbers with the same value are still different numbers, so each one is
+/// given an id in the order it was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct PartNumber {
+    pub id: usize,
+    pub column: usize,
+    pub value: usize,
 }
 
+/// The engine schematic stored as a dense row-major grid
+///
+/// Every cell has a matching entry in `labels` with the id of the number it
+/// belongs to, so looking up the number at a point never needs a search.
 #[derive(Debug, Default)]
 struct ItemMaps {
-    pub symbols: BTreeMap<ItemPoint, char>,
-    pub numbers: BTreeMap<ItemPoint, usize>,
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<char>,
+    pub labels: Vec<Option<usize>>,
+    pub numbers: Vec<PartNumber>,
+    pub symbols: Vec<ItemPoint>,
 }
 
 impl ItemMaps {
     /// Generate from a textual representation of the map
+    /// Short lines are padded with '.' to the width of the longest line
     pub fn from_map(map: String) -> ItemMaps {
-        let mut items = ItemMaps::default();
+        let lines: Vec<Vec<char>> = map.lines().map(|line| line.chars().collect()).collect();
+        let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+        let height = lines.len();
+
+        let mut items = ItemMaps {
+            width,
+            height,
+            cells: vec!['.'; width * height],
+            labels: vec![None; width * height],
+            ..Default::default()
+        };
+
+        for (row, line) in lines.iter().enumerate() {
+            let mut current_number: Option<usize> = None;
+            for (column, &character) in line.iter().enumerate() {
+                let index = row * width + column;
+                items.cells[index] = character;
 
-        for (row, line) in map.lines().enumerate() {
-            let mut current_number = String::new();
-            for (column, character) in line.chars().enumerate() {
                 // If . wrap up any number and move to next item
                 if character == '.' {
-                    current_number.clear();
-                    continue;
+                    current_number = None;
                 } else if character.is_ascii_digit() {
-                    current_number.push(character);
-
-                    // we've come to the end of a number
-                    // Add the parsed number to the map
-                    // We know it will parse as we have checked each digit
-                    let parsed_num = current_number.parse::<usize>().unwrap();
-
-                    // Add the number to each coordinate in its range
-                    for offset in 0..current_number.len() {
-                        items.numbers.insert(
-                            ItemPoint {
-                                column: column as i32 - offset as i32,
-                                row: row as i32,
-                            },
-                            parsed_num,
-                        );
-                    }
+                    // start a new number if we aren't already in one
+                    let id = *current_number.get_or_insert_with(|| {
+                        items.numbers.push(PartNumber {
+                            id: items.numbers.len(),
+                            column,
+                            value: 0,
+                        });
+                        items.numbers.len() - 1
+                    });
+
+                    let number = &mut items.numbers[id];
+                    number.value = number.value * 10 + (character as u8 - b'0') as usize;
+                    items.labels[index] = Some(id);
                 } else {
                     // it must be a symbol
-                    items.symbols.insert(
-                        ItemPoint {
-                            column: column as i32,
-                            row: row as i32,
-                        },
-                        character,
-                    );
-                    current_number.clear();
+                    items.symbols.push(ItemPoint { row, column });
+                    current_number = None;
                 }
             }
         }
@@ -121,53 +169,218 @@ impl ItemMaps {
         items
     }
 
-    /// Get part numbers for a map
-    /// A part number is valid if its positionally next to a symbol
-    pub fn get_part_numbers(&self) -> Vec<usize> {
-        let mut part_numbers = Vec::new();
-
-        for point in self.symbols.keys() {
-            let surrounding_points = point.get_surrounding_points();
-            let mut matching_numbers = BTreeSet::new();
-            for surrounding_point in surrounding_points {
-                if let Some(part_number) = self.numbers.get(&surrounding_point) {
-                    matching_numbers.insert(*part_number);
-                }
+    /// Get the character at a point
+    pub fn get(&self, point: &ItemPoint) -> char {
+        self.cells[point.row * self.width + point.column]
+    }
+
+    /// Get the numbers next to a point
+    /// Each number is only included once, no matter how many of its digits touch the point
+    ///
+    /// Numbers only run left to right, so a number can only be repeated by the
+    /// cell directly before it in the same row.
+    pub fn get_surrounding_numbers(
+        &self,
+        point: &ItemPoint,
+    ) -> impl Iterator<Item = &PartNumber> + '_ {
+        let rows = point.row.saturating_sub(1)..=(point.row + 1).min(self.height - 1);
+        let columns = point.column.saturating_sub(1)..=(point.column + 1).min(self.width - 1);
+
+        rows.flat_map(move |row| {
+            let mut previous = None;
+            columns.clone().filter_map(move |column| {
+                let label = self.labels[row * self.width + column];
+                let is_new = label != previous;
+                previous = label;
+                label.filter(|_| is_new)
+            })
+        })
+        .map(|id| &self.numbers[id])
+    }
+
+    /// Find the symbols that match a predicate, along with their part numbers
+    ///
+    /// Arguments:
+    /// - is_symbol: Returns true for the symbols to include
+    /// - is_count: Returns true for the number of adjacent part numbers to include
+    pub fn query_symbols(
+        &self,
+        is_symbol: impl Fn(char) -> bool,
+        is_count: impl Fn(usize) -> bool,
+    ) -> Vec<SymbolMatch> {
+        self.symbols
+            .iter()
+            .filter(|point| is_symbol(self.get(point)))
+            .map(|point| SymbolMatch {
+                point: *point,
+                symbol: self.get(point),
+                numbers: self.get_surrounding_numbers(point).copied().collect(),
+            })
+            .filter(|symbol_match| is_count(symbol_match.numbers.len()))
+            .collect()
+    }
+
+    /// Flag each number by id if it is next to at least one symbol
+    fn get_attached_ids(&self) -> Vec<bool> {
+        let mut attached = vec![false; self.numbers.len()];
+
+        for point in &self.symbols {
+            for number in self.get_surrounding_numbers(point) {
+                attached[number.id] = true;
             }
-            // add any numbers to the result
-            part_numbers.extend(matching_numbers.iter());
         }
 
-        part_numbers
+        attached
+    }
+
+    /// Get the numbers that are not next to any symbol
+    pub fn get_unattached_numbers(&self) -> Vec<PartNumber> {
+        let attached = self.get_attached_ids();
+
+        self.numbers
+            .iter()
+            .filter(|number| !attached[number.id])
+            .copied()
+            .collect()
+    }
+
+    /// Group part numbers by the symbol they are attached to
+    /// A part number next to more than one symbol is included in each group
+    pub fn group_parts_by_symbol(&self) -> BTreeMap<char, Vec<PartNumber>> {
+        let mut groups: BTreeMap<char, Vec<PartNumber>> = BTreeMap::new();
+
+        for symbol_match in self.query_symbols(|_| true, |count| count > 0) {
+            groups
+                .entry(symbol_match.symbol)
+                .or_default()
+                .extend(symbol_match.numbers);
+        }
+
+        groups
+    }
+
+    /// Get part numbers for a map, in the order they were found
+    /// A part number is valid if its positionally next to a symbol, and is only
+    /// counted once no matter how many symbols it's next to
+    pub fn get_part_numbers(&self) -> Vec<usize> {
+        let attached = self.get_attached_ids();
+
+        self.numbers
+            .iter()
+            .filter(|number| attached[number.id])
+            .map(|number| number.value)
+            .collect()
     }
 
     /// Get gear ratios for a map
     /// A gear is the '*' symbol, as long as only two numbers are near it
     pub fn get_gear_ratios(&self) -> Vec<usize> {
-        let mut gear_ratios = Vec::new();
-
-        for (point, _) in self.symbols.iter().filter(|s| *s.1 == '*') {
-            let surrounding_points = point.get_surrounding_points();
-            let mut matching_numbers = BTreeSet::new();
-            for surrounding_point in surrounding_points {
-                if let Some(part_number) = self.numbers.get(&surrounding_point) {
-                    matching_numbers.insert(*part_number);
+        self.query_symbols(|symbol| symbol == '*', |count| count == 2)
+            .iter()
+            .map(SymbolMatch::ratio)
+            .collect()
+    }
+
+    /// Render the schematic, highlighting what was counted
+    ///
+    /// In ANSI style, counted part numbers are green, ignored numbers are red,
+    /// gears are yellow and other symbols are cyan. In plain style, counted
+    /// part numbers are wrapped in brackets.
+    ///
+    /// Arguments:
+    /// - style: How to highlight the schematic
+    pub fn render(&self, style: RenderStyle) -> String {
+        let attached = self.get_attached_ids();
+        let gears: BTreeSet<ItemPoint> = self
+            .query_symbols(|symbol| symbol == '*', |count| count == 2)
+            .iter()
+            .map(|gear| gear.point)
+            .collect();
+
+        let mut output = String::new();
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let point = ItemPoint { row, column };
+                let index = row * self.width + column;
+                let character = self.get(&point);
+
+                let Some(id) = self.labels[index] else {
+                    match (style, character) {
+                        (RenderStyle::Ansi, '.') | (RenderStyle::Plain, _) => {
+                            output.push(character)
+                        }
+                        (RenderStyle::Ansi, _) => {
+                            let color = if gears.contains(&point) {
+                                ANSI_YELLOW
+                            } else {
+                                ANSI_CYAN
+                            };
+                            output.push_str(&format!("{}{}{}", color, character, ANSI_RESET));
+                        }
+                    }
+                    continue;
+                };
+
+                let is_start = column == self.numbers[id].column;
+                let is_end = column + 1 == self.width || self.labels[index + 1] != Some(id);
+
+                if is_start {
+                    output.push_str(match (style, attached[id]) {
+                        (RenderStyle::Ansi, true) => ANSI_GREEN,
+                        (RenderStyle::Ansi, false) => ANSI_RED,
+                        (RenderStyle::Plain, true) => "[",
+                        (RenderStyle::Plain, false) => "",
+                    });
+                }
+                output.push(character);
+                if is_end {
+                    output.push_str(match (style, attached[id]) {
+                        (RenderStyle::Ansi, _) => ANSI_RESET,
+                        (RenderStyle::Plain, true) => "]",
+                        (RenderStyle::Plain, false) => "",
+                    });
                 }
             }
-            // add any numbers to the result as long as there are just two
-            if matching_numbers.len() == 2 {
-                gear_ratios.push(matching_numbers.iter().product());
-            }
+            output.push('\n');
         }
 
-        gear_ratios
+        output
+    }
+}
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_RED: &str = "\x1b[31m";
+const ANSI_GREEN: &str = "\x1b[32m";
+const ANSI_YELLOW: &str = "\x1b[33m";
+const ANSI_CYAN: &str = "\x1b[36m";
+
+/// How to highlight a rendered schematic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum RenderStyle {
+    /// Color with ANSI escape codes
+    Ansi,
+    /// Wrap counted part numbers in brackets
+    Plain,
+}
+
+/// A symbol found by a query and the part numbers next to it
+#[derive(Debug, PartialEq, Eq)]
+struct SymbolMatch {
+    pub point: ItemPoint,
+    pub symbol: char,
+    pub numbers: Vec<PartNumber>,
+}
+
+impl SymbolMatch {
+    /// The product of all the part numbers next to the symbol
+    pub fn ratio(&self) -> usize {
+        self.numbers.iter().map(|number| number.value).product()
     }
 }
 
 #[cfg(test)]
 mod tests_day_03 {
-    use super::{ItemMaps, ItemPoint};
-    use std::collections::BTreeMap;
+    use super::{ItemMaps, ItemPoint, PartNumber, RenderStyle, SymbolMatch};
 
     #[test]
     fn test_from_map() {
@@ -182,67 +395,80 @@ mod tests_day_03 {
 ...$.*....
 .664.598.."#;
 
-        let symbols: BTreeMap<ItemPoint, char> = BTreeMap::from([
-            (ItemPoint { row: 1, column: 3 }, '*'),
-            (ItemPoint { row: 3, column: 6 }, '#'),
-            (ItemPoint { row: 4, column: 3 }, '*'),
-            (ItemPoint { row: 5, column: 5 }, '+'),
-            (ItemPoint { row: 8, column: 3 }, '$'),
-            (ItemPoint { row: 8, column: 5 }, '*'),
-        ]);
-
-        let numbers: BTreeMap<ItemPoint, usize> = BTreeMap::from([
-            (ItemPoint { row: 0, column: 0 }, 467),
-            (ItemPoint { row: 0, column: 1 }, 467),
-            (ItemPoint { row: 0, column: 2 }, 467),
-            (ItemPoint { row: 0, column: 5 }, 114),
-            (ItemPoint { row: 0, column: 6 }, 114),
-            (ItemPoint { row: 0, column: 7 }, 114),
-            (ItemPoint { row: 2, column: 2 }, 35),
-            (ItemPoint { row: 2, column: 3 }, 35),
-            (ItemPoint { row: 2, column: 6 }, 633),
-            (ItemPoint { row: 2, column: 7 }, 633),
-            (ItemPoint { row: 2, column: 8 }, 633),
-            (ItemPoint { row: 4, column: 0 }, 617),
-            (ItemPoint { row: 4, column: 1 }, 617),
-            (ItemPoint { row: 4, column: 2 }, 617),
-            (ItemPoint { row: 5, column: 7 }, 58),
-            (ItemPoint { row: 5, column: 8 }, 58),
-            (ItemPoint { row: 6, column: 2 }, 592),
-            (ItemPoint { row: 6, column: 3 }, 592),
-            (ItemPoint { row: 6, column: 4 }, 592),
-            (ItemPoint { row: 7, column: 6 }, 755),
-            (ItemPoint { row: 7, column: 7 }, 755),
-            (ItemPoint { row: 7, column: 8 }, 755),
-            (ItemPoint { row: 9, column: 1 }, 664),
-            (ItemPoint { row: 9, column: 2 }, 664),
-            (ItemPoint { row: 9, column: 3 }, 664),
-            (ItemPoint { row: 9, column: 5 }, 598),
-            (ItemPoint { row: 9, column: 6 }, 598),
-            (ItemPoint { row: 9, column: 7 }, 598),
-        ]);
+        let symbols = vec![
+            ItemPoint { row: 1, column: 3 },
+            ItemPoint { row: 3, column: 6 },
+            ItemPoint { row: 4, column: 3 },
+            ItemPoint { row: 5, column: 5 },
+            ItemPoint { row: 8, column: 3 },
+            ItemPoint { row: 8, column: 5 },
+        ];
+
+        let number = |id, column, value| PartNumber { id, column, value };
+        let numbers = vec![
+            number(0, 0, 467),
+            number(1, 5, 114),
+            number(2, 2, 35),
+            number(3, 6, 633),
+            number(4, 0, 617),
+            number(5, 7, 58),
+            number(6, 2, 592),
+            number(7, 6, 755),
+            number(8, 1, 664),
+            number(9, 5, 598),
+        ];
 
         let items = ItemMaps::from_map(String::from(input));
 
+        assert_eq!((items.width, items.height), (10, 10));
         assert_eq!(items.symbols, symbols);
+        assert_eq!(
+            symbols
+                .iter()
+                .map(|point| items.get(point))
+                .collect::<String>(),
+            "*#*+$*"
+        );
         assert_eq!(items.numbers, numbers);
+        assert_eq!(
+            items.labels[..10],
+            [
+                Some(0),
+                Some(0),
+                Some(0),
+                None,
+                None,
+                Some(1),
+                Some(1),
+                Some(1),
+                None,
+                None
+            ]
+        );
     }
 
     #[test]
-    fn test_get_surrounding_points() {
-        assert_eq!(
-            ItemPoint { row: 4, column: 0 }.get_surrounding_points(),
-            vec![
-                ItemPoint { row: 3, column: -1 }, // -1 -1
-                ItemPoint { row: 3, column: 0 },  // -1  0
-                ItemPoint { row: 3, column: 1 },  // -1  1
-                ItemPoint { row: 4, column: -1 }, //  0 -1
-                ItemPoint { row: 4, column: 1 },  //  0  1
-                ItemPoint { row: 5, column: -1 }, //  1 -1
-                ItemPoint { row: 5, column: 0 },  //  1  0
-                ItemPoint { row: 5, column: 1 },  //  1  1
-            ]
-        )
+    fn test_get_surrounding_numbers() {
+        let input = r#"12.3
+*...
+45.6
+..7*"#;
+
+        let items = ItemMaps::from_map(String::from(input));
+        let values = |point: ItemPoint| {
+            items
+                .get_surrounding_numbers(&point)
+                .map(|number| number.value)
+                .collect::<Vec<_>>()
+        };
+
+        // on the edge of the map
+        assert_eq!(values(ItemPoint { row: 1, column: 0 }), vec![12, 45]);
+        // in the corner of the map
+        assert_eq!(values(ItemPoint { row: 3, column: 3 }), vec![6, 7]);
+        // touching more than one digit of the same number
+        assert_eq!(values(ItemPoint { row: 1, column: 1 }), vec![12, 45]);
+        assert_eq!(values(ItemPoint { row: 2, column: 2 }), vec![45, 6, 7]);
     }
 
     #[test]
@@ -261,11 +487,49 @@ mod tests_day_03 {
         let items = ItemMaps::from_map(String::from(input));
 
         assert_eq!(
-            items.get_part_numbers(), // [35, 467, 617, 664, 592, 598, 755, 633]
-            vec![35, 467, 617, 664, 592, 598, 755, 633]
+            items.get_part_numbers(),
+            vec![467, 35, 633, 617, 592, 755, 664, 598]
         )
     }
 
+    #[test]
+    fn test_get_part_numbers_with_duplicate_values() {
+        let input = r#"35.....
+..*....
+...35.."#;
+
+        let items = ItemMaps::from_map(String::from(input));
+
+        assert_eq!(items.get_part_numbers(), vec![35, 35]);
+        assert_eq!(items.get_gear_ratios(), vec![1225]);
+    }
+
+    #[test]
+    fn test_non_ascii_symbols() {
+        let items = ItemMaps::from_map(String::from("12€\n.é..4"));
+
+        assert_eq!(items.width, 5);
+        assert_eq!(
+            items.symbols,
+            vec![
+                ItemPoint { row: 0, column: 2 },
+                ItemPoint { row: 1, column: 1 }
+            ]
+        );
+        assert_eq!(items.get(&ItemPoint { row: 0, column: 2 }), '€');
+        assert_eq!(items.get_part_numbers(), vec![12]);
+        assert_eq!(items.render(RenderStyle::Plain), "[12]€..\n.é..4\n");
+    }
+
+    #[test]
+    fn test_get_part_numbers_shared_by_symbols() {
+        let items = ItemMaps::from_map(String::from("*12*"));
+        assert_eq!(items.get_part_numbers(), vec![12]);
+
+        let items = ItemMaps::from_map(String::from("#..\n.7.\n..$"));
+        assert_eq!(items.get_part_numbers(), vec![7]);
+    }
+
     #[test]
     fn test_get_gear_ratios() {
         let input = r#"467..114..
@@ -286,4 +550,129 @@ mod tests_day_03 {
             vec![16345, 451490]
         )
     }
+
+    #[test]
+    fn test_query_symbols() {
+        let input = r#"1.2..7..
+.#...#..
+3.4.5.6.
+....7..."#;
+
+        let items = ItemMaps::from_map(String::from(input));
+        let number = |id, column, value| PartNumber { id, column, value };
+
+        // triple junctions
+        let triples = items.query_symbols(|symbol| symbol == '#', |count| count == 3);
+        assert_eq!(
+            triples,
+            vec![SymbolMatch {
+                point: ItemPoint { row: 1, column: 5 },
+                symbol: '#',
+                numbers: vec![number(2, 5, 7), number(5, 4, 5), number(6, 6, 6)],
+            }]
+        );
+        assert_eq!(triples.iter().map(SymbolMatch::ratio).sum::<usize>(), 210);
+
+        assert_eq!(
+            items
+                .query_symbols(|symbol| symbol == '#', |count| count >= 3)
+                .len(),
+            2
+        );
+        assert!(items
+            .query_symbols(|symbol| symbol == '*', |_| true)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_get_unattached_numbers() {
+        let input = r#"467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598.."#;
+
+        let items = ItemMaps::from_map(String::from(input));
+
+        assert_eq!(
+            items
+                .get_unattached_numbers()
+                .iter()
+                .map(|number| number.value)
+                .collect::<Vec<_>>(),
+            vec![114, 58]
+        );
+    }
+
+    #[test]
+    fn test_group_parts_by_symbol() {
+        let input = r#"467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598.."#;
+
+        let items = ItemMaps::from_map(String::from(input));
+        let groups = items.group_parts_by_symbol();
+
+        assert_eq!(
+            groups
+                .iter()
+                .map(|(symbol, numbers)| (
+                    *symbol,
+                    numbers.iter().map(|number| number.value).collect()
+                ))
+                .collect::<Vec<(char, Vec<usize>)>>(),
+            vec![
+                ('#', vec![633]),
+                ('$', vec![664]),
+                ('*', vec![467, 35, 617, 755, 598]),
+                ('+', vec![592]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let input = r#"467..114..
+...*......
+..35..633.
+......#...
+617*......"#;
+
+        let items = ItemMaps::from_map(String::from(input));
+
+        assert_eq!(
+            items.render(RenderStyle::Plain),
+            r#"[467]..114..
+...*......
+..[35]..[633].
+......#...
+[617]*......
+"#
+        );
+
+        assert_eq!(
+            items.render(RenderStyle::Ansi).lines().next(),
+            Some("\x1b[32m467\x1b[0m..\x1b[31m114\x1b[0m..")
+        );
+        assert_eq!(
+            items.render(RenderStyle::Ansi).lines().nth(1),
+            Some("...\x1b[33m*\x1b[0m......")
+        );
+        assert_eq!(
+            items.render(RenderStyle::Ansi).lines().nth(3),
+            Some("......\x1b[36m#\x1b[0m...")
+        );
+    }
 }
@@ -1,6 +1,7 @@
 use clap::Parser;
 use std::{
-    collections::HashMap, fmt::Debug, fs::read_to_string, path::PathBuf, process, str::FromStr,
+    collections::BTreeSet, fmt::Debug, fs::read_to_string, ops::Range, path::PathBuf, process,
+    str::FromStr,
 };
 
 use thiserror::Error;
@@ -8,6 +9,9 @@ use thiserror::Error;
 #[derive(Parser)]
 struct Cli {
     input_file: PathBuf,
+    /// Print how many copies of each card were won, and which cards won them
+    #[arg(long)]
+    trace: bool,
 }
 
 fn main() {
@@ -16,32 +20,47 @@ fn main() {
 
     // Read file from CLI arg
     if let Ok(file) = read_to_string(&args.input_file) {
-        let cards = file
+        let cards = match file
             .lines()
-            .flat_map(ScratchOffCard::from_str)
-            .collect::<Vec<ScratchOffCard>>();
+            .filter(|line| !line.trim().is_empty())
+            .map(ScratchOffCard::from_str)
+            .collect::<Result<Vec<ScratchOffCard>, _>>()
+        {
+            Ok(cards) => cards,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        };
 
-        let part_1_answer = cards.iter().fold(0, |acc, card| acc + card.points());
-        let part_2_answer = cards
+        let issues = validate_cards(&cards);
+        for issue in &issues {
+            match issue.severity() {
+                Severity::Warning => eprintln!("warning: {}", issue),
+                Severity::Error => eprintln!("error: {}", issue),
+            }
+        }
+        if issues
             .iter()
-            .fold(HashMap::new(), |mut hash, card| {
-                // get current total
-                let total_current = hash.get(&card.id).unwrap_or(&0) + 1;
-
-                // set final total for current id
-                hash.insert(card.id, total_current);
-
-                // get winning cards
-                for won_id in card.won_ids() {
-                    // add cards to the count + number of current cards
-                    let total = hash.get(&won_id).unwrap_or(&0);
-                    hash.insert(won_id, total + total_current);
-                }
+            .any(|issue| issue.severity() == Severity::Error)
+        {
+            process::exit(1);
+        }
 
-                hash
-            })
-            .values()
-            .sum::<i32>(); // add up the values
+        let part_1_answer = cards.iter().fold(0, |acc, card| acc + card.points());
+        let cascade = cascade(&cards, args.trace);
+        let part_2_answer = cascade.total;
+
+        for card_trace in &cascade.trace {
+            println!(
+                "Card {}: {} original + {} copies = {} from {:?}",
+                card_trace.id,
+                card_trace.originals,
+                card_trace.copies,
+                card_trace.originals + card_trace.copies,
+                card_trace.contributors
+            );
+        }
 
         println!("Part 1: {}\nPart 2: {}", part_1_answer, part_2_answer);
     } else {
@@ -50,21 +69,240 @@ fn main() {
     }
 }
 
+/// How one card ended up in the final count
+#[derive(Debug, PartialEq, Eq)]
+struct CardTrace {
+    pub id: usize,
+    pub originals: u128,
+    pub copies: u128,
+    /// The ids of the cards that won copies of this card
+    pub contributors: Vec<usize>,
+}
+
+/// The result of playing out all the scratchcards
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Cascade {
+    pub total: u128,
+    pub trace: Vec<CardTrace>,
+}
+
+/// Play the cards, where each match wins a copy of one of the following cards
+///
+/// Cards are played in table order, and wins that would run past the end of the
+/// table are dropped.
+///
+/// Arguments:
+/// - cards: The table of cards
+/// - with_trace: Record how many copies of each card were won and by which cards
+fn cascade(cards: &[ScratchOffCard], with_trace: bool) -> Cascade {
+    // every card starts with the original
+    let mut counts = vec![1_u128; cards.len()];
+    let mut contributors = vec![vec![]; if with_trace { cards.len() } else { 0 }];
+
+    for (index, card) in cards.iter().enumerate() {
+        // won cards are the ones after this card in the table
+        let won = card
+            .won_ids()
+            .map(|won_id| index + won_id - card.id)
+            .take_while(|&won_index| won_index < cards.len());
+        for won_index in won {
+            counts[won_index] += counts[index];
+            if with_trace {
+                contributors[won_index].push(card.id);
+            }
+        }
+    }
+
+    Cascade {
+        total: counts.iter().sum(),
+        trace: contributors
+            .into_iter()
+            .zip(cards.iter().zip(&counts))
+            .map(|(contributors, (card, count))| CardTrace {
+                id: card.id,
+                originals: 1,
+                copies: count - 1,
+                contributors,
+            })
+            .collect(),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Severity {
+    Warning,
+    Error,
+}
+
+/// Something odd about a card that parsed fine
+#[derive(Debug, Error, PartialEq, Eq)]
+enum CardIssue {
+    #[error("Card {card_id}: {number} appears more than once in the {list} numbers")]
+    DuplicateNumber {
+        card_id: usize,
+        list: &'static str,
+        number: usize,
+    },
+    #[error("Card {card_id}: expected {expected:?} winning and card numbers, found {found:?}")]
+    InconsistentColumns {
+        card_id: usize,
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    #[error("Card {card_id}: expected card id {expected}")]
+    OutOfSequence { card_id: usize, expected: usize },
+}
+
+impl CardIssue {
+    /// Duplicates only skew the score, but a broken table makes the cascade wrong
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::DuplicateNumber { .. } => Severity::Warning,
+            Self::InconsistentColumns { .. } | Self::OutOfSequence { .. } => Severity::Error,
+        }
+    }
+}
+
+/// Check a table of cards for anything that might throw off the answers
+///
+/// Every card should have the same number of winning and card numbers as the
+/// first card, no repeated numbers within a list, and ids that count up by one.
+///
+/// Arguments:
+/// - cards: The table of cards
+fn validate_cards(cards: &[ScratchOffCard]) -> Vec<CardIssue> {
+    let mut issues = vec![];
+    let Some(first) = cards.first() else {
+        return issues;
+    };
+    let expected_columns = (first.winning_numbers.len(), first.card_numbers.len());
+
+    for (index, card) in cards.iter().enumerate() {
+        if card.id != first.id + index {
+            issues.push(CardIssue::OutOfSequence {
+                card_id: card.id,
+                expected: first.id + index,
+            });
+        }
+
+        let columns = (card.winning_numbers.len(), card.card_numbers.len());
+        if columns != expected_columns {
+            issues.push(CardIssue::InconsistentColumns {
+                card_id: card.id,
+                expected: expected_columns,
+                found: columns,
+            });
+        }
+
+        for (list, numbers) in [
+            ("winning", &card.winning_numbers),
+            ("card", &card.card_numbers),
+        ] {
+            let mut seen = NumberSet::default();
+            let mut reported = NumberSet::default();
+            for &number in numbers {
+                if seen.contains(number) && !reported.contains(number) {
+                    issues.push(CardIssue::DuplicateNumber {
+                        card_id: card.id,
+                        list,
+                        number,
+                    });
+                    reported.insert(number);
+                }
+                seen.insert(number);
+            }
+        }
+    }
+
+    issues
+}
+
+/// The numbers below this are stored as bits, and any above it in a tree
+const NUMBER_SET_BITS: usize = 1 << 12;
+
+/// A set of numbers stored as bits, one bit per possible number
+///
+/// Card numbers are small, so a couple of words covers them and checking a
+/// number is a single bit test. The odd huge number goes in a tree instead,
+/// so it can't make the bits take up more than a few hundred bytes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct NumberSet {
+    bits: Vec<u128>,
+    large: BTreeSet<usize>,
+}
+
+impl NumberSet {
+    /// Add a number to the set
+    pub fn insert(&mut self, num: usize) {
+        if num >= NUMBER_SET_BITS {
+            self.large.insert(num);
+            return;
+        }
+
+        let (word, bit) = (num / 128, num % 128);
+        if word >= self.bits.len() {
+            self.bits.resize(word + 1, 0);
+        }
+        self.bits[word] |= 1 << bit;
+    }
+
+    /// Check if a number is in the set
+    pub fn contains(&self, num: usize) -> bool {
+        if num >= NUMBER_SET_BITS {
+            return self.large.contains(&num);
+        }
+
+        self.bits
+            .get(num / 128)
+            .is_some_and(|word| word & (1 << (num % 128)) != 0)
+    }
+}
+
+impl FromIterator<usize> for NumberSet {
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        let mut set = Self::default();
+        for num in iter {
+            set.insert(num);
+        }
+        set
+    }
+}
+
 #[derive(Debug, Default, PartialEq, Eq)]
 struct ScratchOffCard {
     pub id: usize,
     pub winning_numbers: Vec<usize>,
     pub card_numbers: Vec<usize>,
+    winning_set: NumberSet,
+    match_count: usize,
 }
 
 impl ScratchOffCard {
-    /// Calculates the IDs of the cards you win
-    pub fn won_ids(&self) -> Vec<usize> {
-        let matches = self.matching_numbers();
+    /// Create a card, working out its matches up front
+    ///
+    /// Arguments:
+    /// - id: The card id
+    /// - winning_numbers: The numbers that win
+    /// - card_numbers: The numbers you have
+    pub fn new(id: usize, winning_numbers: Vec<usize>, card_numbers: Vec<usize>) -> Self {
+        let winning_set: NumberSet = winning_numbers.iter().copied().collect();
+        let match_count = card_numbers
+            .iter()
+            .filter(|&&num| winning_set.contains(num))
+            .count();
+
+        Self {
+            id,
+            winning_numbers,
+            card_numbers,
+            winning_set,
+            match_count,
+        }
+    }
 
-        (0..matches.len())
-            .map(|index| self.id + index + 1)
-            .collect::<Vec<usize>>()
+    /// Calculates the IDs of the cards you win
+    pub fn won_ids(&self) -> Range<usize> {
+        self.id + 1..self.id + 1 + self.match_count
     }
 
     /// Calculates the point value of the card
@@ -73,22 +311,12 @@ impl ScratchOffCard {
     /// the winning numbers and the card numbers.
     /// The first match is worth 1 point, each additional match doubles the points
     pub fn points(&self) -> usize {
-        let matches = self.matching_numbers();
-
-        if !matches.is_empty() {
-            2_usize.pow(matches.len() as u32 - 1)
+        if self.match_count > 0 {
+            2_usize.pow(self.match_count as u32 - 1)
         } else {
             0
         }
     }
-
-    /// Finds the numbers that match the winning numbers
-    pub fn matching_numbers(&self) -> Vec<&usize> {
-        self.card_numbers
-            .iter()
-            .filter(|num| self.winning_numbers.contains(num))
-            .collect::<Vec<&usize>>()
-    }
 }
 
 #[derive(Debug, Error)]
@@ -103,6 +331,23 @@ enum ScratchOffCardParseError {
     MissingNumberInfo(String),
     #[error("Unknown number format for Card Id {0}: {1}")]
     UnknownNumberFormat(String, String),
+    #[error("Unable to parse number for Card Id {0}: {1}")]
+    ParseNumber(String, String),
+}
+
+/// Parse a list of space separated numbers, failing on anything that isn't a number
+///
+/// Arguments:
+/// - card_id: The id of the card, for error reporting
+/// - s: The list of numbers
+fn parse_numbers(card_id: usize, s: &str) -> Result<Vec<usize>, ScratchOffCardParseError> {
+    s.split_whitespace()
+        .map(|num| {
+            num.parse::<usize>().map_err(|_| {
+                ScratchOffCardParseError::ParseNumber(card_id.to_string(), String::from(num))
+            })
+        })
+        .collect()
 }
 
 impl FromStr for ScratchOffCard {
@@ -118,7 +363,7 @@ impl FromStr for ScratchOffCard {
     ///     card_numbers: [ 83, 86, 6, 31, 17, 9, 48, 53]
     /// }
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut card = Self::default();
+        let card_id_num;
 
         // split on : to get the card info and the numbers
         let mut colon_split = s.split(':');
@@ -137,14 +382,14 @@ impl FromStr for ScratchOffCard {
             return Err(Self::Err::MissingCardId(String::from(card_info.unwrap())));
         }
 
-        if let Ok(card_id_num) = card_id.trim().parse::<usize>() {
-            card.id = card_id_num;
+        if let Ok(id) = card_id.trim().parse::<usize>() {
+            card_id_num = id;
         } else {
             return Err(Self::Err::ParseCardId(String::from(card_info.unwrap())));
         }
 
         if number_info.unwrap().is_empty() {
-            return Err(Self::Err::MissingNumberInfo(card.id.to_string()));
+            return Err(Self::Err::MissingNumberInfo(card_id_num.to_string()));
         }
 
         // split on | to get the winning and card numbers
@@ -154,24 +399,15 @@ impl FromStr for ScratchOffCard {
 
         if winning_numbers_str.is_none() || card_numbers_str.is_none() {
             return Err(Self::Err::UnknownNumberFormat(
-                card.id.to_string(),
+                card_id_num.to_string(),
                 String::from(s),
             ));
         }
 
-        card.winning_numbers = winning_numbers_str
-            .unwrap()
-            .split(' ')
-            .flat_map(|num| num.parse::<usize>())
-            .collect::<Vec<usize>>();
+        let winning_numbers = parse_numbers(card_id_num, winning_numbers_str.unwrap())?;
+        let card_numbers = parse_numbers(card_id_num, card_numbers_str.unwrap())?;
 
-        card.card_numbers = card_numbers_str
-            .unwrap()
-            .split(' ')
-            .flat_map(|num| num.parse::<usize>())
-            .collect::<Vec<usize>>();
-
-        Ok(card)
+        Ok(Self::new(card_id_num, winning_numbers, card_numbers))
     }
 }
 
@@ -179,17 +415,101 @@ impl FromStr for ScratchOffCard {
 mod tests_day_04 {
     use std::str::FromStr;
 
-    use super::ScratchOffCard;
+    use super::{
+        cascade, validate_cards, CardIssue, CardTrace, NumberSet, ScratchOffCard,
+        ScratchOffCardParseError, Severity,
+    };
+
+    const EXAMPLE: &str = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
+
+    fn example_cards() -> Vec<ScratchOffCard> {
+        EXAMPLE
+            .lines()
+            .map(|line| ScratchOffCard::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_cascade() {
+        let cards = example_cards();
+
+        let result = cascade(&cards, false);
+        assert_eq!(result.total, 30);
+        assert!(result.trace.is_empty());
+
+        let result = cascade(&cards, true);
+        assert_eq!(result.total, 30);
+        assert_eq!(
+            result.trace[3],
+            CardTrace {
+                id: 4,
+                originals: 1,
+                copies: 7,
+                contributors: vec![1, 2, 3]
+            }
+        );
+        assert_eq!(
+            result
+                .trace
+                .iter()
+                .map(|card_trace| card_trace.originals + card_trace.copies)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 4, 8, 14, 1]
+        );
+    }
+
+    #[test]
+    fn test_cascade_stops_at_table_end() {
+        // the last card would win copies of cards that don't exist
+        let cards = [
+            ScratchOffCard::new(1, vec![1, 2], vec![1]),
+            ScratchOffCard::new(2, vec![1, 2], vec![1, 2]),
+        ];
+
+        let result = cascade(&cards, true);
+        assert_eq!(result.total, 3);
+        assert_eq!(result.trace[1].contributors, vec![1]);
+    }
+
+    #[test]
+    fn test_number_set() {
+        let set: NumberSet = [0, 5, 127, 128, 300].into_iter().collect();
+
+        assert!(set.contains(0));
+        assert!(set.contains(5));
+        assert!(set.contains(127));
+        assert!(set.contains(128));
+        assert!(set.contains(300));
+        assert!(!set.contains(1));
+        assert!(!set.contains(129));
+        assert!(!set.contains(1000));
+
+        // huge numbers don't need a bit for every number below them
+        let set: NumberSet = [41, 99999999999999].into_iter().collect();
+        assert!(set.contains(41));
+        assert!(set.contains(99999999999999));
+        assert!(!set.contains(99999999999998));
+        assert_eq!(set.bits.len(), 1);
+
+        let card =
+            ScratchOffCard::from_str("Card 1: 41 99999999999999 | 83 99999999999999").unwrap();
+        assert_eq!(card.points(), 1);
+    }
 
     #[test]
     fn test_from_str() {
         assert_eq!(
             ScratchOffCard::from_str("Card 1: 41 48 83 86 17 | 83 86 6 31 17 9 48 53").unwrap(),
-            ScratchOffCard {
-                id: 1,
-                winning_numbers: vec![41, 48, 83, 86, 17],
-                card_numbers: vec![83, 86, 6, 31, 17, 9, 48, 53]
-            }
+            ScratchOffCard::new(
+                1,
+                vec![41, 48, 83, 86, 17],
+                vec![83, 86, 6, 31, 17, 9, 48, 53]
+            )
         );
     }
 
@@ -197,56 +517,98 @@ mod tests_day_04 {
     fn test_from_str_with_multiple_spaces() {
         assert_eq!(
             ScratchOffCard::from_str("Card   1: 41  3 83 86 17 | 83 86 6 31 17  9 48 53").unwrap(),
-            ScratchOffCard {
-                id: 1,
-                winning_numbers: vec![41, 3, 83, 86, 17],
-                card_numbers: vec![83, 86, 6, 31, 17, 9, 48, 53]
-            }
+            ScratchOffCard::new(
+                1,
+                vec![41, 3, 83, 86, 17],
+                vec![83, 86, 6, 31, 17, 9, 48, 53]
+            )
         );
     }
 
     #[test]
-    fn test_matching_numbers() {
+    fn test_from_str_rejects_bad_numbers() {
+        assert!(matches!(
+            ScratchOffCard::from_str("Card 1: 41 1a 83 | 83 86"),
+            Err(ScratchOffCardParseError::ParseNumber(id, num)) if id == "1" && num == "1a"
+        ));
+    }
+
+    #[test]
+    fn test_validate_cards() {
+        assert!(validate_cards(&example_cards()).is_empty());
+
+        let cards = [
+            ScratchOffCard::new(1, vec![1, 2, 3], vec![4, 5, 6, 7]),
+            ScratchOffCard::new(2, vec![1, 1, 3], vec![4, 4, 4, 7]),
+            ScratchOffCard::new(4, vec![1, 2], vec![4, 5, 6, 7]),
+        ];
+
+        let issues = validate_cards(&cards);
+        assert_eq!(
+            issues,
+            vec![
+                CardIssue::DuplicateNumber {
+                    card_id: 2,
+                    list: "winning",
+                    number: 1
+                },
+                CardIssue::DuplicateNumber {
+                    card_id: 2,
+                    list: "card",
+                    number: 4
+                },
+                CardIssue::OutOfSequence {
+                    card_id: 4,
+                    expected: 3
+                },
+                CardIssue::InconsistentColumns {
+                    card_id: 4,
+                    expected: (3, 4),
+                    found: (2, 4)
+                },
+            ]
+        );
+        assert_eq!(issues[0].severity(), Severity::Warning);
+        assert_eq!(issues[2].severity(), Severity::Error);
+    }
+
+    #[test]
+    fn test_match_count() {
         assert_eq!(
             ScratchOffCard::from_str("Card 1: 41 48 83 86 17 | 83 86 6 31 17 9 48 53")
                 .unwrap()
-                .matching_numbers(),
-            vec![&83, &86, &17, &48]
+                .match_count,
+            4
         );
-
         assert_eq!(
             ScratchOffCard::from_str("Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19")
                 .unwrap()
-                .matching_numbers(),
-            vec![&61, &32]
+                .match_count,
+            2
         );
-
         assert_eq!(
             ScratchOffCard::from_str("Card 3: 1 21 53 59 44 | 69 82 63 72 16 21 14 1")
                 .unwrap()
-                .matching_numbers(),
-            vec![&21, &1]
+                .match_count,
+            2
         );
-
         assert_eq!(
             ScratchOffCard::from_str("Card 4: 41 92 73 84 69 | 59 84 76 51 58 5 54 83")
                 .unwrap()
-                .matching_numbers(),
-            vec![&84]
+                .match_count,
+            1
         );
-
-        assert!(
+        assert_eq!(
             ScratchOffCard::from_str("Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36")
                 .unwrap()
-                .matching_numbers()
-                .is_empty()
+                .match_count,
+            0
         );
-
-        assert!(
+        assert_eq!(
             ScratchOffCard::from_str("Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11")
                 .unwrap()
-                .matching_numbers()
-                .is_empty()
+                .match_count,
+            0
         );
     }
 
@@ -300,8 +662,16 @@ mod tests_day_04 {
         assert_eq!(
             ScratchOffCard::from_str("Card 1: 41 48 83 86 17 | 83 86 6 31 17 9 48 53")
                 .unwrap()
-                .won_ids(),
+                .won_ids()
+                .collect::<Vec<_>>(),
             vec![2, 3, 4, 5]
         );
+
+        assert_eq!(
+            ScratchOffCard::from_str("Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19")
+                .unwrap()
+                .match_count,
+            2
+        );
     }
 }
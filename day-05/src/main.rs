@@ -1,11 +1,39 @@
 use clap::Parser;
-use std::{collections::BTreeMap, fs::read_to_string, path::PathBuf, process, str::FromStr};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs::read_to_string,
+    ops::{Add, Sub},
+    path::PathBuf,
+    process,
+    str::FromStr,
+};
 
 use thiserror::Error;
 
 #[derive(Parser)]
 struct Cli {
     input_file: PathBuf,
+    /// Solve part 2 by searching upward from the lowest location instead
+    #[arg(long)]
+    inverse: bool,
+    /// Trace the part 2 answer back to the seed it came from
+    #[arg(long)]
+    explain: bool,
+    /// Walk through each table in turn instead of using the composed table
+    #[arg(long, conflicts_with = "inverse")]
+    walk: bool,
+    /// Only check the almanac for problems, without solving it
+    #[arg(long)]
+    validate: bool,
+    /// The category the listed seeds belong to
+    #[arg(long, default_value = "seed")]
+    from: String,
+    /// The category to find the lowest value of
+    #[arg(long, default_value = "location")]
+    to: String,
+    /// Print the category graph for Graphviz, without solving it
+    #[arg(long)]
+    dot: bool,
 }
 
 fn main() {
@@ -15,139 +43,378 @@ fn main() {
     // Read file from CLI arg
     if let Ok(file) = read_to_string(&args.input_file) {
         let almanac = Almanac::from_str(&file).expect("almanac to parse");
+        if args.dot {
+            print!("{}", almanac.to_dot());
+            return;
+        }
+
+        // only a broken chain is an error, and then there's no answer to give
+        let mut unreachable = false;
+        for issue in almanac.validate(&args.from, &args.to) {
+            let label = match issue.severity() {
+                Severity::Warning => "warning",
+                Severity::Error => {
+                    unreachable = true;
+                    "error"
+                }
+            };
+            eprintln!("{label}: {issue}");
+        }
+        if unreachable {
+            eprintln!("No chain of tables leads from {} to {}", args.from, args.to);
+            process::exit(1);
+        }
+        if args.validate {
+            println!("Almanac is valid");
+            return;
+        }
+
+        let seed_to_location = almanac
+            .compose(&args.from, &args.to)
+            .expect("seeds to reach locations");
 
         let part_1_answer = almanac
             .seeds
             .iter()
-            .flat_map(|seed| almanac.get_type_value(*seed, "location"))
+            .map(|seed| {
+                if args.walk {
+                    almanac.get_type_value(*seed, &args.from, &args.to).unwrap()
+                } else {
+                    seed_to_location.get_relation(seed)
+                }
+            })
             .min()
             .unwrap();
 
-        let part_2_answer = SeedRange::reduce_ranges(
+        let seed_ranges = almanac
+            .seeds
+            // create pairs
+            .chunks(2)
+            // turn them into intervals of seeds
+            .map(|chunk| Interval::with_length(chunk[0], chunk[1]))
+            .collect::<IntervalSet<_>>();
+
+        let part_2_answer = if args.inverse {
             almanac
-                .seeds
-                // create pairs
-                .chunks(2)
-                // turn them into SeedRanges
-                .map(|chunk| SeedRange::new(chunk[0], chunk[0] + chunk[1]))
-                // convert the ranges to locations
-                .flat_map(|range| almanac.get_type_ranges(range, "location"))
-                .collect::<Vec<_>>(),
-        )
-        // take the first one (as reduce_ranges sorts them by start)
-        .first()
-        .unwrap()
-        // this is the smallest starting range for a location!
-        .start;
+                .lowest_reachable_value(&seed_ranges, &args.from, &args.to)
+                .unwrap()
+        } else {
+            // convert the seeds to locations
+            if args.walk {
+                almanac
+                    .get_type_ranges(seed_ranges.clone(), &args.from, &args.to)
+                    .unwrap()
+            } else {
+                seed_to_location.update_range(&seed_ranges)
+            }
+            // this is the smallest location!
+            .min()
+            .unwrap()
+        };
 
         println!("Part 1: {}\nPart 2: {}", part_1_answer, part_2_answer);
+
+        if args.explain {
+            // find a planted seed that ends up at the answer
+            let seed = almanac
+                .get_source_values(part_2_answer, &args.from, &args.to)
+                .into_iter()
+                .find(|seed| seed_ranges.contains(seed));
+
+            if let Some(seed) = seed {
+                let trace = almanac
+                    .trace(seed, &args.from, &args.to)
+                    .iter()
+                    .map(|(type_name, value)| format!("{} {}", type_name, value))
+                    .collect::<Vec<_>>();
+                println!("Part 2 Explained: {}", trace.join(" -> "));
+            } else {
+                println!(
+                    "Part 2 Explained: no planted seed reaches {}",
+                    part_2_answer
+                );
+            }
+        }
     } else {
         eprintln!("Could not read file: {}", args.input_file.display());
         process::exit(1);
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-struct SeedRange {
-    start: usize,
-    num_values: usize,
-    end: usize,
+/// A half-open interval of values, from `start` up to but not including `end`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Interval<T> {
+    start: T,
+    end: T,
 }
 
-impl Ord for SeedRange {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.start.cmp(&other.start)
+impl<T> Interval<T>
+where
+    T: Copy + Ord + Add<Output = T> + Sub<Output = T>,
+{
+    /// Creates a new Interval with a given start and end
+    ///
+    /// Arguments:
+    /// - start: The first value in the interval
+    /// - end: The first value after the interval
+    fn new(start: T, end: T) -> Self {
+        debug_assert!(start <= end, "interval should not end before it starts");
+        Self { start, end }
     }
-}
 
-impl PartialOrd for SeedRange {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+    /// Creates a new Interval from its first value and the number of values in it
+    ///
+    /// Arguments:
+    /// - start: The first value in the interval
+    /// - length: How many values are in the interval
+    fn with_length(start: T, length: T) -> Self {
+        Self::new(start, start + length)
+    }
+
+    /// The number of values in the interval
+    fn len(&self) -> T {
+        self.end - self.start
+    }
+
+    /// Checks if the interval has no values at all
+    fn is_empty(&self) -> bool {
+        self.start >= self.end
     }
-}
 
-impl SeedRange {
-    /// Creates a new SeedRange with a given start and end
+    /// Checks if a value is inside the interval
     ///
     /// Arguments:
-    /// - start: When the range starts
-    /// - end: When the range ends, inclusive
-    fn new(start: usize, end: usize) -> Self {
-        Self {
-            start,
-            end,
-            num_values: end - start + 1,
-        }
+    /// - value: The value to look for
+    fn contains(&self, value: &T) -> bool {
+        self.start <= *value && *value < self.end
     }
 
-    /// Checks if the given range has any overlap
+    /// Get the part of both intervals that overlaps, if any
     ///
     /// Example:
-    /// SeedRange(1, 5) overlaps SeedRange(4, 10) by 4-5
+    /// Interval(1, 5) and Interval(4, 10) overlap by Interval(4, 5)
     ///
     /// Arguments:
-    /// - range: The other range to check
-    fn has_overlap(&self, other: &SeedRange) -> bool {
-        (self.start <= other.start && self.end >= other.start - 1)
-            || (self.start >= other.start && self.start <= other.end + 1)
+    /// - other: The other interval
+    fn intersection(&self, other: &Interval<T>) -> Option<Interval<T>> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+
+        (start < end).then(|| Interval::new(start, end))
     }
 
-    /// Creates a new range that encompasses both ranges
-    ///
-    /// Todo: This should really return a Result and use `Self::has_overlap()`
-    ///       to check if it _should_ merge
+    /// Move the interval so the value at `from` ends up at `to`
     ///
     /// Arguments:
-    /// - other: The other range to merge into
-    fn merge(&self, other: &SeedRange) -> SeedRange {
-        SeedRange::new(
-            if self.start <= other.start {
-                self.start
-            } else {
-                other.start
-            },
-            if self.end >= other.end {
-                self.end
-            } else {
-                other.end
-            },
-        )
+    /// - from: A value before the move
+    /// - to: Where that value should be after the move
+    fn shift(&self, from: T, to: T) -> Interval<T> {
+        if to >= from {
+            Interval::new(self.start + (to - from), self.end + (to - from))
+        } else {
+            Interval::new(self.start - (from - to), self.end - (from - to))
+        }
+    }
+}
+
+/// A set of values, stored as sorted intervals that never overlap or touch
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self { intervals: vec![] }
     }
+}
 
-    /// Give a list of ranges, reduce the list down to the smallest number of ranges
+impl<T> IntervalSet<T>
+where
+    T: Copy + Ord + Add<Output = T> + Sub<Output = T>,
+{
+    /// Build a set from intervals that may overlap or touch, in any order
     ///
     /// Example:
-    /// Given \[SeedRange(1,5), SeedRange(4,10), SeedRange(15, 20)\]
-    /// you should get \[SeedRange(1,10), SeedRange(15, 20)\]
+    /// Given \[Interval(4, 10), Interval(1, 5), Interval(15, 20)\]
+    /// you should get \[Interval(1, 10), Interval(15, 20)\]
     /// because the first two have overlap and can be combined, but the third cannot
     ///
     /// Arguments:
-    /// - ranges: A list of ranges it should try to merge
-    fn reduce_ranges(ranges: Vec<Self>) -> Vec<Self> {
-        let mut sorted = ranges.clone();
+    /// - intervals: The intervals to merge into a set
+    fn coalesce(intervals: impl IntoIterator<Item = Interval<T>>) -> Self {
+        let mut sorted = intervals.into_iter().collect::<Vec<_>>();
         sorted.sort();
-        sorted.into_iter().fold(Vec::new(), |mut acc, range| {
-            if let Some(last) = acc.last_mut() {
-                if last.has_overlap(&range) {
-                    // replace with merged range
-                    *last = last.merge(&range);
-                } else {
-                    acc.push(range);
-                }
+
+        Self::from_sorted(sorted)
+    }
+
+    /// Build a set from intervals that are already sorted by start
+    ///
+    /// Arguments:
+    /// - sorted: The intervals to merge into a set
+    fn from_sorted(sorted: impl IntoIterator<Item = Interval<T>>) -> Self {
+        let mut intervals: Vec<Interval<T>> = vec![];
+
+        for interval in sorted.into_iter().filter(|interval| !interval.is_empty()) {
+            match intervals.last_mut() {
+                // overlapping or touching, so extend the last interval
+                Some(last) if interval.start <= last.end => last.end = last.end.max(interval.end),
+                _ => intervals.push(interval),
+            }
+        }
+
+        Self { intervals }
+    }
+
+    /// Iterate over the intervals in the set, in ascending order
+    fn iter(&self) -> impl Iterator<Item = &Interval<T>> {
+        self.intervals.iter()
+    }
+
+    /// Checks if the set has no values at all
+    fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// The smallest value in the set, if there is one
+    fn min(&self) -> Option<T> {
+        self.intervals.first().map(|interval| interval.start)
+    }
+
+    /// Checks if a value is in the set
+    ///
+    /// Arguments:
+    /// - value: The value to look for
+    fn contains(&self, value: &T) -> bool {
+        let index = self
+            .intervals
+            .partition_point(|interval| interval.end <= *value);
+
+        self.intervals
+            .get(index)
+            .is_some_and(|interval| interval.contains(value))
+    }
+
+    /// Every value that is in either set
+    ///
+    /// Arguments:
+    /// - other: The other set
+    fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut merged = Vec::with_capacity(self.intervals.len() + other.intervals.len());
+        let mut mine = self.intervals.iter().peekable();
+        let mut theirs = other.intervals.iter().peekable();
+
+        // both sides are already sorted, so merge them rather than sorting again
+        while let (Some(a), Some(b)) = (mine.peek(), theirs.peek()) {
+            if a.start <= b.start {
+                merged.push(**a);
+                mine.next();
             } else {
-                acc.push(range);
+                merged.push(**b);
+                theirs.next();
             }
+        }
+        merged.extend(mine.chain(theirs).copied());
 
-            acc
-        })
+        Self::from_sorted(merged)
+    }
+
+    /// Every value that is in both sets
+    ///
+    /// Arguments:
+    /// - other: The other set
+    fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut intervals = vec![];
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (&self.intervals[i], &other.intervals[j]);
+            intervals.extend(a.intersection(b));
+
+            // move past whichever interval finishes first
+            if a.end <= b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        Self { intervals }
+    }
+
+    /// Every value that is in this set but not the other
+    ///
+    /// Arguments:
+    /// - other: The set of values to remove
+    fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut intervals = vec![];
+        let mut first = 0;
+
+        for interval in &self.intervals {
+            // skip anything that finishes before this interval starts
+            while first < other.intervals.len() && other.intervals[first].end <= interval.start {
+                first += 1;
+            }
+
+            let mut start = interval.start;
+            for removed in other.intervals[first..]
+                .iter()
+                .take_while(|removed| removed.start < interval.end)
+            {
+                if start < removed.start {
+                    intervals.push(Interval::new(start, removed.start));
+                }
+                start = start.max(removed.end);
+            }
+
+            if start < interval.end {
+                intervals.push(Interval::new(start, interval.end));
+            }
+        }
+
+        Self { intervals }
+    }
+
+    /// Move every value in the set so the value at `from` ends up at `to`
+    ///
+    /// Arguments:
+    /// - from: A value before the move
+    /// - to: Where that value should be after the move
+    fn shift(&self, from: T, to: T) -> IntervalSet<T> {
+        Self {
+            intervals: self
+                .intervals
+                .iter()
+                .map(|interval| interval.shift(from, to))
+                .collect(),
+        }
+    }
+}
+
+impl<T> From<Interval<T>> for IntervalSet<T>
+where
+    T: Copy + Ord + Add<Output = T> + Sub<Output = T>,
+{
+    fn from(interval: Interval<T>) -> Self {
+        Self::from_sorted([interval])
+    }
+}
+
+impl<T> FromIterator<Interval<T>> for IntervalSet<T>
+where
+    T: Copy + Ord + Add<Output = T> + Sub<Output = T>,
+{
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        Self::coalesce(iter)
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 struct SeedRelationRange {
-    source_start: usize,
-    destination_start: usize,
-    range: usize,
+    source_start: u64,
+    destination_start: u64,
+    range: u64,
 }
 
 impl SeedRelationRange {
@@ -155,67 +422,57 @@ impl SeedRelationRange {
     ///
     /// Arguments:
     /// - id: The id to look for
-    fn get_destination(&self, id: &usize) -> Option<usize> {
-        if id < &self.source_start || id > &(self.source_start + self.range) {
-            return None;
-        }
-
-        Some(self.destination_start + (id - self.source_start))
+    fn get_destination(&self, id: &u64) -> Option<u64> {
+        self.source()
+            .contains(id)
+            .then(|| self.destination_start + (id - self.source_start))
     }
 
-    /// Transform a given range into a list of ranges, with the appropriate portions
-    /// updated based on the relation
+    /// Get the source value that maps to a destination, if it exists in the range
     ///
     /// Arguments:
-    /// - range: The seed range to update
-    ///
-    /// Returns a tuple of (modified ranges, remainder ranges)
-    fn update_range(&self, range: SeedRange) -> (Vec<SeedRange>, Vec<SeedRange>) {
-        let mut modified = Vec::new();
-        let mut remainder = Vec::new();
-        let my_end = self.source_start + self.range;
+    /// - id: The destination id to look for
+    fn get_source(&self, id: &u64) -> Option<u64> {
+        self.destination()
+            .contains(id)
+            .then(|| self.source_start + (id - self.destination_start))
+    }
 
-        // check for any before range
-        if range.start < self.source_start {
-            remainder.push(SeedRange::new(
-                range.start,
-                if range.end < self.source_start {
-                    range.end
-                } else {
-                    self.source_start - 1
-                },
-            ));
-        }
+    /// The source values covered by the relation
+    fn source(&self) -> Interval<u64> {
+        Interval::with_length(self.source_start, self.range)
+    }
 
-        // check for any in range
-        if range.start < my_end && range.end >= self.source_start {
-            modified.push(SeedRange::new(
-                if range.start > self.source_start {
-                    self.destination_start + (range.start - self.source_start)
-                } else {
-                    self.destination_start
-                },
-                if range.end < my_end {
-                    self.destination_start + (range.end - self.source_start)
-                } else {
-                    self.destination_start + my_end - self.source_start - 1
-                },
-            ));
-        }
+    /// The destination values covered by the relation
+    fn destination(&self) -> Interval<u64> {
+        Interval::with_length(self.destination_start, self.range)
+    }
 
-        // check for any after range
-        if range.end > my_end {
-            remainder.push(SeedRange::new(
-                if range.start <= my_end {
-                    my_end
-                } else {
-                    range.start
-                },
-                range.end,
-            ));
-        }
+    /// Map destination values back to the source values they came from
+    /// Any values outside the destination are dropped
+    ///
+    /// Arguments:
+    /// - values: The destination values to map back
+    fn inverse_range(&self, values: &IntervalSet<u64>) -> IntervalSet<u64> {
+        values
+            .intersection(&self.destination().into())
+            .shift(self.destination_start, self.source_start)
+    }
 
-        (modified, remainder)
+    /// Split a set of ids into the ones the relation moves and the ones it doesn't
+    ///
+    /// Arguments:
+    /// - ids: The ids to update
+    ///
+    /// Returns a tuple of (modified ids, remainder ids)
+    fn update_range(&self, ids: &IntervalSet<u64>) -> (IntervalSet<u64>, IntervalSet<u64>) {
+        let source = IntervalSet::from(self.source());
+
+        (
+            ids.intersection(&source)
+                .shift(self.source_start, self.destination_start),
+            ids.difference(&source),
+        )
     }
 }
 
@@ -254,9 +511,9 @@ impl FromStr for SeedRelationRange {
         {
             // parse numbers
             if let (Ok(destination_start), Ok(source_start), Ok(range)) = (
-                destination.parse::<usize>(),
-                source.parse::<usize>(),
-                range.parse::<usize>(),
+                destination.parse::<u64>(),
+                source.parse::<u64>(),
+                range.parse::<u64>(),
             ) {
                 Ok(Self {
                     destination_start,
@@ -277,13 +534,20 @@ struct SeedRelationTable {
     from: String,
     to: String,
     ranges: Vec<SeedRelationRange>,
+    /// The line the table's title is on, each range is on a line after it
+    line: usize,
 }
 
 impl SeedRelationTable {
+    /// The name of the table, as it appears in its title
+    fn name(&self) -> String {
+        format!("{}-to-{}", self.from, self.to)
+    }
+
     /// Get the relational value of the id
     /// If its in the range, do the translation
     /// otherwise the id stays the same
-    fn get_relation(&self, id: &usize) -> usize {
+    fn get_relation(&self, id: &u64) -> u64 {
         for range in &self.ranges {
             if let Some(dest) = range.get_destination(id) {
                 return dest;
@@ -293,25 +557,171 @@ impl SeedRelationTable {
         *id
     }
 
-    /// Updates a range into a set of ranges based on the range table
-    fn update_range(&self, range: SeedRange) -> Vec<SeedRange> {
-        let mut updated = vec![];
-        let mut remainders = vec![range];
+    /// Get every id that relates to the given value
+    /// This is the inverse of `get_relation`, but more than one id can map to the
+    /// same value, so all of them are returned in ascending order
+    ///
+    /// Arguments:
+    /// - value: The related value to trace back
+    fn get_sources(&self, value: &u64) -> Vec<u64> {
+        let mut sources = self
+            .ranges
+            .iter()
+            .flat_map(|range| range.get_source(value))
+            // unmapped ids relate to themselves
+            .chain(std::iter::once(*value))
+            // skip ids that are really handled by a different range
+            .filter(|id| self.get_relation(id) == *value)
+            .collect::<Vec<_>>();
 
-        for seed_relation_range in &self.ranges {
-            let mut new_remainders = Vec::new();
-            for remainder in remainders {
-                let (new_updated, new_remainder) = seed_relation_range.update_range(remainder);
-                updated.extend(new_updated);
-                new_remainders.extend(new_remainder);
+        sources.sort();
+        sources.dedup();
+
+        sources
+    }
+
+    /// Get the ids that relate to a set of values
+    /// This is the inverse of `update_range`
+    ///
+    /// Arguments:
+    /// - values: The related values to trace back
+    fn inverse_update_range(&self, values: &IntervalSet<u64>) -> IntervalSet<u64> {
+        let mut sources = IntervalSet::default();
+        let mut claimed = IntervalSet::default();
+
+        for relation in &self.ranges {
+            // an earlier range takes priority over this one
+            sources = sources.union(&relation.inverse_range(values).difference(&claimed));
+            claimed = claimed.union(&relation.source().into());
+        }
+
+        // anything not covered by a range relates to itself
+        sources.union(&values.difference(&claimed))
+    }
+
+    /// Split the ids below a limit into non-overlapping relations
+    ///
+    /// Where ranges overlap, the first one wins, just like `get_relation`, and any
+    /// gaps become relations that map to themselves. The result is sorted by source.
+    ///
+    /// Arguments:
+    /// - limit: The first id that is not included
+    fn pieces(&self, limit: u64) -> Vec<SeedRelationRange> {
+        let mut pieces = vec![];
+        let mut uncovered = IntervalSet::from(Interval::new(0, limit));
+
+        for relation in &self.ranges {
+            let source = IntervalSet::from(relation.source());
+
+            pieces.extend(uncovered.intersection(&source).iter().map(|overlap| {
+                SeedRelationRange {
+                    source_start: overlap.start,
+                    destination_start: relation.destination_start
+                        + (overlap.start - relation.source_start),
+                    range: overlap.len(),
+                }
+            }));
+            uncovered = uncovered.difference(&source);
+        }
+
+        pieces.extend(uncovered.iter().map(|range| SeedRelationRange {
+            source_start: range.start,
+            destination_start: range.start,
+            range: range.len(),
+        }));
+        pieces.sort_by_key(|piece| piece.source_start);
+
+        pieces
+    }
+
+    /// The first id past every source and destination in the table
+    fn limit(&self) -> u64 {
+        self.ranges
+            .iter()
+            .map(|range| (range.source_start.max(range.destination_start)) + range.range)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Merge this table with the table that follows it into one equivalent table
+    ///
+    /// Looking up an id in the merged table gives the same value as looking it up
+    /// in this table and then looking that up in the next one.
+    ///
+    /// Arguments:
+    /// - next: The table that maps this table's values onward
+    fn compose(&self, next: &SeedRelationTable) -> SeedRelationTable {
+        // past the limit both tables map ids to themselves
+        let limit = self.limit().max(next.limit());
+        let next_pieces = next.pieces(limit);
+        let mut ranges: Vec<SeedRelationRange> = vec![];
+
+        for piece in self.pieces(limit) {
+            let image = piece.destination();
+
+            // the first piece of the next table that could overlap the image
+            let first = next_pieces.partition_point(|next_piece| {
+                next_piece.source_start + next_piece.range <= image.start
+            });
+
+            for next_piece in next_pieces[first..]
+                .iter()
+                .take_while(|next_piece| next_piece.source_start < image.end)
+            {
+                let Some(overlap) = image.intersection(&next_piece.source()) else {
+                    continue;
+                };
+
+                let source_start = piece.source_start + (overlap.start - piece.destination_start);
+                let destination_start =
+                    next_piece.destination_start + (overlap.start - next_piece.source_start);
+
+                // ids that map to themselves don't need a range
+                if source_start == destination_start {
+                    continue;
+                }
+
+                // join on to the last range if it continues it
+                if let Some(last) = ranges.last_mut() {
+                    if last.source_start + last.range == source_start
+                        && last.destination_start + last.range == destination_start
+                    {
+                        last.range += overlap.len();
+                        continue;
+                    }
+                }
+
+                ranges.push(SeedRelationRange {
+                    source_start,
+                    destination_start,
+                    range: overlap.len(),
+                });
             }
+        }
 
-            remainders = new_remainders;
+        SeedRelationTable {
+            from: self.from.clone(),
+            to: next.to.clone(),
+            ranges,
+            line: self.line,
         }
+    }
+
+    /// Updates a set of ids based on the range table
+    ///
+    /// Arguments:
+    /// - ids: The ids to update
+    fn update_range(&self, ids: &IntervalSet<u64>) -> IntervalSet<u64> {
+        let mut updated = IntervalSet::default();
+        let mut remainder = ids.clone();
 
-        updated.extend(remainders);
+        for seed_relation_range in &self.ranges {
+            let (moved, left) = seed_relation_range.update_range(&remainder);
+            updated = updated.union(&moved);
+            remainder = left;
+        }
 
-        updated
+        updated.union(&remainder)
     }
 }
 
@@ -331,7 +741,8 @@ impl FromStr for SeedRelationTable {
     ///             destination_start: 1,
     ///             range: 3
     ///         }
-    ///     ]
+    ///     ],
+    ///     line: 1
     /// }
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut lines = s.lines();
@@ -340,12 +751,15 @@ impl FromStr for SeedRelationTable {
             return Err(Self::Err::MapTitle(String::new()));
         }
 
-        let mut table = Self::default();
+        let mut table = Self {
+            line: 1,
+            ..Self::default()
+        };
 
         if let Some(title) = seed_line.unwrap().split_ascii_whitespace().next() {
             let mut to_split = title.split('-');
             let from = to_split.next();
-            let to = to_split.last();
+            let to = to_split.next_back();
 
             if let (Some(from), Some(to)) = (from, to) {
                 table.from = String::from(from);
@@ -365,10 +779,139 @@ impl FromStr for SeedRelationTable {
     }
 }
 
+/// A table built by composing a chain of tables
+///
+/// The ranges are sorted by source and never overlap, so lookups can use a
+/// binary search instead of checking every range.
+#[derive(Debug, PartialEq, Eq)]
+struct ComposedTable {
+    table: SeedRelationTable,
+}
+
+impl ComposedTable {
+    /// Find the index of the first range that ends after the id
+    fn first_range_after(&self, id: u64) -> usize {
+        self.table
+            .ranges
+            .partition_point(|range| range.source_start + range.range <= id)
+    }
+
+    /// Get the relational value of the id
+    /// If its in a range, do the translation
+    /// otherwise the id stays the same
+    fn get_relation(&self, id: &u64) -> u64 {
+        self.table
+            .ranges
+            .get(self.first_range_after(*id))
+            .and_then(|range| range.get_destination(id))
+            .unwrap_or(*id)
+    }
+
+    /// Updates a set of ids based on the composed table
+    ///
+    /// Arguments:
+    /// - ids: The ids to update
+    fn update_range(&self, ids: &IntervalSet<u64>) -> IntervalSet<u64> {
+        let mut updated = vec![];
+        let mut covered = vec![];
+
+        for interval in ids.iter() {
+            for relation in self.table.ranges[self.first_range_after(interval.start)..]
+                .iter()
+                .take_while(|relation| relation.source_start < interval.end)
+            {
+                if let Some(overlap) = interval.intersection(&relation.source()) {
+                    updated.push(overlap.shift(relation.source_start, relation.destination_start));
+                    covered.push(overlap);
+                }
+            }
+        }
+
+        // the ids outside every range map to themselves
+        updated.extend(ids.difference(&IntervalSet::coalesce(covered)).iter());
+
+        IntervalSet::coalesce(updated)
+    }
+}
+
+/// Whether an almanac issue still lets the values be followed to the target
+#[derive(Debug, PartialEq, Eq)]
+enum Severity {
+    Warning,
+    Error,
+}
+
+/// A table, range or chain of tables that doesn't fit the category graph
+///
+/// Each issue points at the line of the range or table header it's about.
+#[derive(Debug, Error, PartialEq, Eq)]
+enum AlmanacIssue {
+    #[error("{table} line {line}: same source range as line {first_line}")]
+    DuplicateSource {
+        table: String,
+        line: usize,
+        first_line: usize,
+    },
+    #[error(
+        "{table} line {line}: source overlaps line {earlier_line} for ids {}..{}, which takes priority",
+        .overlap.start,
+        .overlap.end
+    )]
+    OverlappingSources {
+        table: String,
+        line: usize,
+        earlier_line: usize,
+        overlap: Interval<u64>,
+    },
+    #[error("line {line}: nothing maps {category} onward, so it never reaches {target}")]
+    MissingTable {
+        category: String,
+        target: String,
+        line: usize,
+    },
+    #[error("line {line}: the tables go round in a loop: {}", .chain.join(" -> "))]
+    CyclicChain { chain: Vec<String>, line: usize },
+    #[error("line {line}: nothing maps {category} onward, so this branch never reaches {target}")]
+    DeadEndBranch {
+        category: String,
+        target: String,
+        line: usize,
+    },
+    #[error("line {line}: this branch goes round in a loop: {}", .chain.join(" -> "))]
+    LoopingBranch { chain: Vec<String>, line: usize },
+    #[error("{table} line {line}: not on any chain of tables from {from} to {target}")]
+    UnreachableTable {
+        table: String,
+        from: String,
+        target: String,
+        line: usize,
+    },
+}
+
+impl AlmanacIssue {
+    /// Overlaps, stray tables and side branches still give an answer, but the
+    /// only chain from the start to the target being broken doesn't
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::DuplicateSource { .. }
+            | Self::OverlappingSources { .. }
+            | Self::UnreachableTable { .. }
+            | Self::DeadEndBranch { .. }
+            | Self::LoopingBranch { .. } => Severity::Warning,
+            Self::MissingTable { .. } | Self::CyclicChain { .. } => Severity::Error,
+        }
+    }
+}
+
+/// The tables of an almanac, as a graph of categories
+///
+/// Each category can have any number of tables leading out of it, so values can
+/// be followed from any category to any other one that a chain of tables reaches.
 #[derive(Debug, Default)]
 struct Almanac {
-    seeds: Vec<usize>,
-    tables: BTreeMap<String, SeedRelationTable>,
+    seeds: Vec<u64>,
+    /// The tables leading out of each category, in the order they were written
+    tables: BTreeMap<String, Vec<SeedRelationTable>>,
 }
 
 impl Almanac {
@@ -380,8 +923,8 @@ impl Almanac {
         for item in s.split_ascii_whitespace() {
             match item.trim() {
                 "seeds:" => {} // do nothing
-                num if num.parse::<usize>().is_ok() => {
-                    self.seeds.push(num.parse::<usize>().unwrap());
+                num if num.parse::<u64>().is_ok() => {
+                    self.seeds.push(num.parse::<u64>().unwrap());
                 }
                 _ => return Err(ParseError::SeedId(String::from(item))),
             }
@@ -390,145 +933,547 @@ impl Almanac {
         Ok(())
     }
 
-    /// Get type value for a seed
-    /// Follows the mapping, until it finds a type value for a seed id
+    /// Add a table to the graph, after any other tables from the same category
+    ///
+    /// Arguments:
+    /// - table: The table to add
+    fn add_table(&mut self, table: SeedRelationTable) {
+        self.tables
+            .entry(table.from.clone())
+            .or_default()
+            .push(table);
+    }
+
+    /// Iterate over every table, grouped by the category they lead out of
+    fn all_tables(&self) -> impl Iterator<Item = &SeedRelationTable> {
+        self.tables.values().flatten()
+    }
+
+    /// The tables leading out of a category
+    ///
+    /// Arguments:
+    /// - category: The name of the category
+    fn tables_from(&self, category: &str) -> &[SeedRelationTable] {
+        self.tables.get(category).map_or(&[], Vec::as_slice)
+    }
+
+    /// Find the shortest chain of tables between two categories
+    ///
+    /// If there's more than one, the tables written first are preferred.
     ///
     /// Arguments:
-    /// - seed_id: The id of the seed
-    /// - type_name: The name of the type to look for
-    fn get_type_value(&self, seed_id: usize, type_name: &str) -> Option<usize> {
-        let mut current_type = Some("seed");
-        let mut current_id = seed_id;
-        while current_type.is_some() {
-            if let Some(table) = self.tables.get(current_type.unwrap()) {
-                current_id = table.get_relation(&current_id);
-                current_type.replace(&table.to);
-                if current_type == Some(type_name) {
-                    return Some(current_id);
+    /// - from: The name of the category to start at
+    /// - to: The name of the category to finish at
+    fn find_path(&self, from: &str, to: &str) -> Option<Vec<&SeedRelationTable>> {
+        let mut came_from: BTreeMap<&str, Option<&SeedRelationTable>> = BTreeMap::new();
+        let mut queue = VecDeque::from([from]);
+        came_from.insert(from, None);
+
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                // walk back to the start to recover the path
+                let mut path = vec![];
+                let mut current = to;
+                while let Some(Some(table)) = came_from.get(current) {
+                    path.push(*table);
+                    current = &table.from;
+                }
+                path.reverse();
+
+                return Some(path);
+            }
+
+            for table in self.tables_from(category) {
+                if !came_from.contains_key(table.to.as_str()) {
+                    came_from.insert(&table.to, Some(table));
+                    queue.push_back(&table.to);
                 }
-            } else {
-                current_type = None;
             }
         }
 
         None
     }
 
-    /// Get the new type ranges of a given range and type
-    /// Follows the mapping, until it finds a type value for the range
+    /// Look for ranges that are shadowed and chains that never reach the target
+    ///
+    /// Within a table, source ranges shouldn't overlap, since only the first one
+    /// is ever used. Gaps between them are fine, as those ids map to themselves.
+    /// Every category reached from the start should also lead on to the target
+    /// without looping back on itself, and every table should be used on the way.
     ///
     /// Arguments:
-    /// - range: The range of seeds
-    /// - type_name: The name of the type to look for
-    fn get_type_ranges(&self, range: SeedRange, type_name: &str) -> Vec<SeedRange> {
-        let mut current_type = Some("seed");
-        let mut final_ranges = vec![range];
-        eprintln!(
-            "Type {:?}: {:?}",
-            current_type.unwrap(),
-            &final_ranges
-                .iter()
-                .map(|r| (r.start, r.end))
-                .collect::<Vec<_>>()
-        );
-
-        while current_type.is_some() {
-            if let Some(table) = self.tables.get(current_type.unwrap()) {
-                // current_id = table.get_relation(&current_id);
-                final_ranges = SeedRange::reduce_ranges(
-                    final_ranges
-                        .into_iter()
-                        .flat_map(|seed_range| table.update_range(seed_range))
-                        .collect(),
-                );
-                current_type.replace(&table.to);
-                eprintln!(
-                    "Type {:?}: {:?}",
-                    current_type.unwrap(),
-                    &final_ranges
-                        .iter()
-                        .map(|r| (r.start, r.end))
-                        .collect::<Vec<_>>()
-                );
-                if current_type == Some(type_name) {
-                    break;
+    /// - from: The name of the category to start at
+    /// - target: The name of the category the chains should reach
+    fn validate(&self, from: &str, target: &str) -> Vec<AlmanacIssue> {
+        let mut issues = vec![];
+
+        for table in self.all_tables() {
+            for (index, relation) in table.ranges.iter().enumerate() {
+                let line = table.line + index + 1;
+                let source = relation.source();
+
+                for (earlier_index, earlier) in table.ranges[..index].iter().enumerate() {
+                    let earlier_line = table.line + earlier_index + 1;
+
+                    if source.is_empty() {
+                        break;
+                    } else if earlier.source() == source {
+                        issues.push(AlmanacIssue::DuplicateSource {
+                            table: table.name(),
+                            line,
+                            first_line: earlier_line,
+                        });
+                        break;
+                    } else if let Some(overlap) = earlier.source().intersection(&source) {
+                        issues.push(AlmanacIssue::OverlappingSources {
+                            table: table.name(),
+                            line,
+                            earlier_line,
+                            overlap,
+                        });
+                    }
                 }
-            } else {
-                current_type = None;
             }
         }
 
-        final_ranges
-    }
-}
-
-impl FromStr for Almanac {
-    type Err = ParseError;
-
-    /// Parse an Almanac from a textual representation of an almanac
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut almanac = Almanac::default();
+        // a dead end or a loop only breaks the answers if there's no other way
+        // through, otherwise it's a side branch that's never taken
+        let blocked = self.find_path(from, target).is_none();
+
+        // follow every chain from the start, which is on the first line, keeping
+        // track of the line that led to each category and the next table to try
+        let mut chain: Vec<(&str, usize, usize)> = vec![(from, 1, 0)];
+        let mut seen = vec![from];
+        let mut followed = vec![];
+        while let Some((category, line, next)) = chain.last().copied() {
+            let outgoing = self.tables_from(category);
+            if category == target || next == outgoing.len() {
+                if category != target && outgoing.is_empty() {
+                    let category = String::from(category);
+                    let target = String::from(target);
+                    issues.push(match blocked {
+                        true => AlmanacIssue::MissingTable {
+                            category,
+                            target,
+                            line,
+                        },
+                        false => AlmanacIssue::DeadEndBranch {
+                            category,
+                            target,
+                            line,
+                        },
+                    });
+                }
+                chain.pop();
+                continue;
+            }
 
-        let mut groups = s.split("\n\n");
-        almanac
-            .parse_seeds(groups.next().unwrap())
-            .expect("seeds to parse");
+            let table = &outgoing[next];
+            chain.last_mut().unwrap().2 += 1;
+            followed.push(table.line);
+
+            if chain.iter().any(|(category, _, _)| *category == table.to) {
+                let looped = chain
+                    .iter()
+                    .map(|(category, _, _)| String::from(*category))
+                    .chain(std::iter::once(table.to.clone()))
+                    .collect();
+                issues.push(match blocked {
+                    true => AlmanacIssue::CyclicChain {
+                        chain: looped,
+                        line: table.line,
+                    },
+                    false => AlmanacIssue::LoopingBranch {
+                        chain: looped,
+                        line: table.line,
+                    },
+                });
+            } else if !seen.contains(&table.to.as_str()) {
+                seen.push(&table.to);
+                chain.push((&table.to, table.line, 0));
+            }
+        }
 
-        for group in groups {
-            let table = SeedRelationTable::from_str(group)?;
-            almanac.tables.insert(table.from.clone(), table);
+        for table in self.all_tables() {
+            if !followed.contains(&table.line) {
+                issues.push(AlmanacIssue::UnreachableTable {
+                    table: table.name(),
+                    from: String::from(from),
+                    target: String::from(target),
+                    line: table.line,
+                });
+            }
         }
 
-        Ok(almanac)
+        issues
     }
-}
 
-#[cfg(test)]
+    /// Get the value of one category for an id of another
+    /// Follows the shortest chain of tables between them
+    ///
+    /// Arguments:
+    /// - id: The id in the starting category
+    /// - from: The name of the category the id belongs to
+    /// - to: The name of the category to look for
+    fn get_type_value(&self, id: u64, from: &str, to: &str) -> Option<u64> {
+        Some(
+            self.find_path(from, to)?
+                .iter()
+                .fold(id, |current_id, table| table.get_relation(&current_id)),
+        )
+    }
+
+    /// Compose the tables between two categories into a single table
+    ///
+    /// Arguments:
+    /// - from: The name of the category to map from
+    /// - to: The name of the category to map to
+    fn compose(&self, from: &str, to: &str) -> Option<ComposedTable> {
+        // composing with an empty table sorts and splits up the ranges
+        let identity = SeedRelationTable {
+            from: String::from(from),
+            to: String::from(from),
+            ..SeedRelationTable::default()
+        };
+        let composed = self
+            .find_path(from, to)?
+            .iter()
+            .fold(identity, |composed, table| composed.compose(table));
+
+        Some(ComposedTable { table: composed })
+    }
+
+    /// Get the ids of one category that end up with a value of another
+    /// Follows the shortest chain of tables between them backwards
+    ///
+    /// Arguments:
+    /// - value: The value in the final category
+    /// - from: The name of the category to trace back to
+    /// - to: The name of the category the value belongs to
+    fn get_source_values(&self, value: u64, from: &str, to: &str) -> Vec<u64> {
+        let Some(path) = self.find_path(from, to) else {
+            return vec![];
+        };
+
+        let mut current_ids = vec![value];
+        for table in path.iter().rev() {
+            current_ids = current_ids
+                .iter()
+                .flat_map(|id| table.get_sources(id))
+                .collect();
+        }
+
+        current_ids.sort();
+        current_ids.dedup();
+
+        current_ids
+    }
+
+    /// Get the ids of one category that end up with any of a set of values of another
+    /// Follows the shortest chain of tables between them backwards
+    ///
+    /// Arguments:
+    /// - values: The values in the final category
+    /// - from: The name of the category to trace back to
+    /// - to: The name of the category the values belong to
+    fn get_source_ranges(
+        &self,
+        values: IntervalSet<u64>,
+        from: &str,
+        to: &str,
+    ) -> IntervalSet<u64> {
+        let Some(path) = self.find_path(from, to) else {
+            return IntervalSet::default();
+        };
+
+        path.iter().rev().fold(values, |current_ids, table| {
+            table.inverse_update_range(&current_ids)
+        })
+    }
+
+    /// Find the lowest value of a category reachable from a set of ids of another
+    ///
+    /// Rather than pushing every id forward, this searches upward from the
+    /// lowest value: a value range is reachable if tracing it back lands in
+    /// the starting ids, so a binary search finds the lowest one.
+    ///
+    /// Arguments:
+    /// - ids: The ids to start with, like the seeds that were planted
+    /// - from: The name of the category the ids belong to
+    /// - to: The name of the category to look for
+    fn lowest_reachable_value(&self, ids: &IntervalSet<u64>, from: &str, to: &str) -> Option<u64> {
+        let reaches_ids = |upper: u64| {
+            !self
+                .get_source_ranges(Interval::new(0, upper + 1).into(), from, to)
+                .intersection(ids)
+                .is_empty()
+        };
+
+        // the biggest value a half open range can hold
+        let (mut low, mut high) = (0, u64::MAX - 1);
+        if !reaches_ids(high) {
+            return None;
+        }
+
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if reaches_ids(middle) {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+
+        Some(low)
+    }
+
+    /// Follow an id between two categories, recording the value of each one on the way
+    ///
+    /// Arguments:
+    /// - id: The id in the starting category
+    /// - from: The name of the category the id belongs to
+    /// - to: The name of the category to finish at
+    fn trace(&self, id: u64, from: &str, to: &str) -> Vec<(String, u64)> {
+        let mut trace = vec![(String::from(from), id)];
+        let mut current_id = id;
+
+        for table in self.find_path(from, to).unwrap_or_default() {
+            current_id = table.get_relation(&current_id);
+            trace.push((table.to.clone(), current_id));
+        }
+
+        trace
+    }
+
+    /// Get the values of one category for a set of ids of another
+    /// Follows the shortest chain of tables between them
+    ///
+    /// Arguments:
+    /// - ids: The ids in the starting category
+    /// - from: The name of the category the ids belong to
+    /// - to: The name of the category to look for
+    fn get_type_ranges(
+        &self,
+        ids: IntervalSet<u64>,
+        from: &str,
+        to: &str,
+    ) -> Option<IntervalSet<u64>> {
+        let mut final_ids = ids;
+        for table in self.find_path(from, to)? {
+            final_ids = table.update_range(&final_ids);
+        }
+
+        Some(final_ids)
+    }
+
+    /// Describe the category graph in the DOT language, for Graphviz
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph almanac {\n");
+
+        for table in self.all_tables() {
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"line {}, {} ranges\"];\n",
+                table.from,
+                table.to,
+                table.line,
+                table.ranges.len()
+            ));
+        }
+        dot.push_str("}\n");
+
+        dot
+    }
+}
+
+impl FromStr for Almanac {
+    type Err = ParseError;
+
+    /// Parse an Almanac from a textual representation of an almanac
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut almanac = Almanac::default();
+
+        let mut groups = s.split("\n\n");
+        let seeds = groups.next().unwrap();
+        almanac.parse_seeds(seeds).expect("seeds to parse");
+
+        // each group is followed by a blank line
+        let mut line = seeds.lines().count() + 2;
+        for group in groups {
+            let mut table = SeedRelationTable::from_str(group)?;
+            table.line = line;
+            line += group.lines().count() + 1;
+            almanac.add_table(table);
+        }
+
+        Ok(almanac)
+    }
+}
+
+#[cfg(test)]
 mod tests_day_05 {
     use std::str::FromStr;
 
-    use super::{Almanac, SeedRange, SeedRelationRange, SeedRelationTable};
+    use super::{
+        Almanac, AlmanacIssue, ComposedTable, Interval, IntervalSet, SeedRelationRange,
+        SeedRelationTable, Severity,
+    };
+
+    const EXAMPLE: &str = "seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4";
+
+    /// Build an interval set from (start, end) pairs
+    fn set(intervals: &[(u64, u64)]) -> IntervalSet<u64> {
+        intervals
+            .iter()
+            .map(|(start, end)| Interval::new(*start, *end))
+            .collect()
+    }
 
     #[test]
-    fn test_seed_range_has_overlap() {
+    fn test_interval_intersection() {
+        let interval = Interval::new(10, 20);
+
         // before
-        assert!(!SeedRange::new(10, 20).has_overlap(&SeedRange::new(1, 5)));
-        // adjacent
-        assert!(SeedRange::new(10, 20).has_overlap(&SeedRange::new(1, 9)));
+        assert_eq!(interval.intersection(&Interval::new(1, 5)), None);
+        // touching isn't overlapping
+        assert_eq!(interval.intersection(&Interval::new(0, 10)), None);
+        assert_eq!(interval.intersection(&Interval::new(20, 25)), None);
         // overlap start
-        assert!(SeedRange::new(10, 20).has_overlap(&SeedRange::new(5, 15)));
+        assert_eq!(
+            interval.intersection(&Interval::new(5, 15)),
+            Some(Interval::new(10, 15))
+        );
         // overlap middle
-        assert!(SeedRange::new(10, 20).has_overlap(&SeedRange::new(12, 18)));
+        assert_eq!(
+            interval.intersection(&Interval::new(12, 18)),
+            Some(Interval::new(12, 18))
+        );
         // overlap end
-        assert!(SeedRange::new(10, 20).has_overlap(&SeedRange::new(15, 25)));
-        // adjacent
-        assert!(SeedRange::new(10, 20).has_overlap(&SeedRange::new(21, 25)));
-        // after
-        assert!(!SeedRange::new(10, 20).has_overlap(&SeedRange::new(25, 35)));
+        assert_eq!(
+            interval.intersection(&Interval::new(15, 25)),
+            Some(Interval::new(15, 20))
+        );
+        // starting at zero doesn't underflow
+        assert_eq!(
+            Interval::new(0, 5).intersection(&Interval::new(0, 1)),
+            Some(Interval::new(0, 1))
+        );
     }
 
     #[test]
-    fn test_seed_range_reduce_ranges() {
+    fn test_interval_set_coalesce() {
         assert_eq!(
-            SeedRange::reduce_ranges(vec![
-                SeedRange::new(1, 10),
-                SeedRange::new(5, 15),
-                SeedRange::new(10, 20),
-                SeedRange::new(22, 30),
+            IntervalSet::coalesce([
+                Interval::new(22, 31),
+                Interval::new(1, 11),
+                Interval::new(10, 21),
+                Interval::new(5, 16),
             ]),
-            vec![SeedRange::new(1, 20), SeedRange::new(22, 30)]
+            set(&[(1, 21), (22, 31)])
         );
 
-        // merge adjacent numbers
+        // merge touching intervals, and drop empty ones
         assert_eq!(
-            SeedRange::reduce_ranges(vec![
-                SeedRange::new(1, 10),
-                SeedRange::new(11, 15),
-                SeedRange::new(16, 20),
-                SeedRange::new(22, 30),
+            IntervalSet::coalesce([
+                Interval::new(1, 11),
+                Interval::new(11, 16),
+                Interval::new(16, 21),
+                Interval::new(25, 25),
             ]),
-            vec![SeedRange::new(1, 20), SeedRange::new(22, 30),]
+            set(&[(1, 21)])
+        );
+
+        assert!(IntervalSet::<u64>::coalesce([]).is_empty());
+    }
+
+    #[test]
+    fn test_interval_set_union() {
+        assert_eq!(
+            set(&[(0, 5), (10, 15), (30, 40)]).union(&set(&[(5, 8), (12, 20), (50, 60)])),
+            set(&[(0, 8), (10, 20), (30, 40), (50, 60)])
+        );
+        assert_eq!(set(&[(0, 5)]).union(&set(&[])), set(&[(0, 5)]));
+    }
+
+    #[test]
+    fn test_interval_set_intersection() {
+        assert_eq!(
+            set(&[(0, 10), (20, 30)]).intersection(&set(&[(5, 25), (28, 40)])),
+            set(&[(5, 10), (20, 25), (28, 30)])
+        );
+        assert!(set(&[(0, 10)]).intersection(&set(&[(10, 20)])).is_empty());
+    }
+
+    #[test]
+    fn test_interval_set_difference() {
+        // split in the middle
+        assert_eq!(
+            set(&[(0, 100)]).difference(&set(&[(10, 20), (50, 60)])),
+            set(&[(0, 10), (20, 50), (60, 100)])
+        );
+        // one removed interval spanning several
+        assert_eq!(
+            set(&[(0, 10), (20, 30), (40, 50)]).difference(&set(&[(5, 45)])),
+            set(&[(0, 5), (45, 50)])
+        );
+        // nothing left
+        assert!(set(&[(0, 10)]).difference(&set(&[(0, 10)])).is_empty());
+        // nothing removed
+        assert_eq!(
+            set(&[(0, 10)]).difference(&set(&[(10, 20)])),
+            set(&[(0, 10)])
+        );
+    }
+
+    #[test]
+    fn test_interval_set_shift() {
+        assert_eq!(
+            set(&[(98, 100), (110, 120)]).shift(98, 50),
+            set(&[(50, 52), (62, 72)])
         );
+        assert_eq!(set(&[(0, 2)]).shift(0, 10), set(&[(10, 12)]));
+    }
+
+    #[test]
+    fn test_interval_set_contains() {
+        let intervals = set(&[(0, 5), (10, 15)]);
+
+        assert!(intervals.contains(&0));
+        assert!(intervals.contains(&14));
+        assert!(!intervals.contains(&5));
+        assert!(!intervals.contains(&15));
+        assert_eq!(intervals.min(), Some(0));
     }
 
     #[test]
@@ -560,21 +1505,18 @@ mod tests_day_05 {
         let range = SeedRelationRange::from_str("50 98 2").unwrap();
 
         assert_eq!(
-            range.update_range(SeedRange::new(90, 110)),
-            (
-                vec![SeedRange::new(50, 51),],
-                vec![SeedRange::new(90, 97), SeedRange::new(100, 110),]
-            )
+            range.update_range(&set(&[(90, 111)])),
+            (set(&[(50, 52)]), set(&[(90, 98), (100, 111)]))
         );
 
         assert_eq!(
-            range.update_range(SeedRange::new(1, 10)),
-            (vec![], vec![SeedRange::new(1, 10),])
+            range.update_range(&set(&[(1, 11)])),
+            (set(&[]), set(&[(1, 11)]))
         );
 
         assert_eq!(
-            range.update_range(SeedRange::new(100, 110)),
-            (vec![], vec![SeedRange::new(100, 110),])
+            range.update_range(&set(&[(100, 111)])),
+            (set(&[]), set(&[(100, 111)]))
         );
     }
 
@@ -585,7 +1527,8 @@ mod tests_day_05 {
             SeedRelationTable {
                 from: String::from("seed"),
                 to: String::from("soil"),
-                ranges: Vec::new()
+                ranges: Vec::new(),
+                line: 1
             }
         );
 
@@ -594,7 +1537,8 @@ mod tests_day_05 {
             SeedRelationTable {
                 from: String::from("seed"),
                 to: String::from("soil"),
-                ranges: vec![SeedRelationRange::from_str("0 15 37").unwrap()]
+                ranges: vec![SeedRelationRange::from_str("0 15 37").unwrap()],
+                line: 1
             }
         );
     }
@@ -614,161 +1558,408 @@ mod tests_day_05 {
             .parse_seeds("seeds: 79 14 55 13")
             .expect("should parse seeds");
 
-        almanac.tables.insert(
-            String::from("seed"),
-            SeedRelationTable {
-                from: String::from("seed"),
-                to: String::from("soil"),
-                ranges: vec![
-                    SeedRelationRange::from_str("50 98 2").unwrap(),
-                    SeedRelationRange::from_str("52 50 48").unwrap(),
-                ],
-            },
+        almanac.add_table(SeedRelationTable {
+            from: String::from("seed"),
+            to: String::from("soil"),
+            ranges: vec![
+                SeedRelationRange::from_str("50 98 2").unwrap(),
+                SeedRelationRange::from_str("52 50 48").unwrap(),
+            ],
+            line: 3,
+        });
+
+        almanac.add_table(SeedRelationTable {
+            from: String::from("soil"),
+            to: String::from("fertilizer"),
+            ranges: vec![
+                SeedRelationRange::from_str("0 15 37").unwrap(),
+                SeedRelationRange::from_str("37 52 2").unwrap(),
+                SeedRelationRange::from_str("39 0 15").unwrap(),
+            ],
+            line: 7,
+        });
+
+        assert_eq!(almanac.get_type_value(79, "seed", "fertilizer"), Some(81));
+    }
+
+    #[test]
+    fn test_almanac_get_type_ranges() {
+        let almanac = Almanac::from_str(EXAMPLE).unwrap();
+
+        assert_eq!(
+            almanac.get_type_ranges(set(&[(82, 83)]), "seed", "location"),
+            Some(set(&[(46, 47)]))
         );
 
-        almanac.tables.insert(
-            String::from("soil"),
-            SeedRelationTable {
-                from: String::from("soil"),
-                to: String::from("fertilizer"),
-                ranges: vec![
-                    SeedRelationRange::from_str("0 15 37").unwrap(),
-                    SeedRelationRange::from_str("37 52 2").unwrap(),
-                    SeedRelationRange::from_str("39 0 15").unwrap(),
-                ],
-            },
+        assert_eq!(
+            almanac.get_type_ranges(set(&[(79, 94)]), "seed", "location"),
+            // seed 92 reaches location 68
+            Some(set(&[(46, 56), (60, 61), (68, 69), (82, 85)]))
         );
 
-        assert_eq!(almanac.get_type_value(79, "fertilizer"), Some(81));
+        assert_eq!(
+            almanac.get_type_ranges(set(&[(55, 68)]), "seed", "location"),
+            Some(set(&[(56, 60), (86, 90), (94, 99)]))
+        );
     }
 
     #[test]
-    fn test_almanac_get_type_ranges() {
+    fn test_almanac_from_str() {
+        let almanac = Almanac::from_str(EXAMPLE).unwrap();
+
+        assert_eq!(almanac.seeds, vec![79, 14, 55, 13]);
+        assert_eq!(almanac.tables.len(), 7); // todo: check all the table values?
+        assert_eq!(almanac.tables["seed"][0].line, 3);
+        assert_eq!(almanac.tables["soil"][0].line, 7);
+        assert_eq!(almanac.tables["humidity"][0].line, 31);
+    }
+
+    #[test]
+    fn test_almanac_validate() {
+        assert!(Almanac::from_str(EXAMPLE)
+            .unwrap()
+            .validate("seed", "location")
+            .is_empty());
+
         let almanac = Almanac::from_str(
-            "seeds: 79 14 55 13
+            "seeds: 1 2
 
 seed-to-soil map:
 50 98 2
-52 50 48
+52 50 49
+60 98 2
 
-soil-to-fertilizer map:
-0 15 37
-37 52 2
-39 0 15
+soil-to-water map:
+0 10 5
 
-fertilizer-to-water map:
-49 53 8
-0 11 42
-42 0 7
-57 7 4
+fertilizer-to-light map:
+1 2 3",
+        )
+        .unwrap();
+        let issues = almanac.validate("seed", "location");
 
-water-to-light map:
-88 18 7
-18 25 70
+        assert_eq!(
+            issues,
+            vec![
+                AlmanacIssue::OverlappingSources {
+                    table: String::from("seed-to-soil"),
+                    line: 5,
+                    earlier_line: 4,
+                    overlap: Interval::new(98, 99),
+                },
+                AlmanacIssue::DuplicateSource {
+                    table: String::from("seed-to-soil"),
+                    line: 6,
+                    first_line: 4,
+                },
+                AlmanacIssue::MissingTable {
+                    category: String::from("water"),
+                    target: String::from("location"),
+                    line: 8,
+                },
+                AlmanacIssue::UnreachableTable {
+                    table: String::from("fertilizer-to-light"),
+                    from: String::from("seed"),
+                    target: String::from("location"),
+                    line: 11,
+                },
+            ]
+        );
+        assert_eq!(issues[0].severity(), Severity::Warning);
+        assert_eq!(issues[2].severity(), Severity::Error);
+        assert_eq!(
+            issues[0].to_string(),
+            "seed-to-soil line 5: source overlaps line 4 for ids 98..99, which takes priority"
+        );
 
-light-to-temperature map:
-45 77 23
-81 45 19
-68 64 13
+        let almanac = Almanac::from_str(
+            "seeds: 1 2
 
-temperature-to-humidity map:
-0 69 1
-1 0 69
+seed-to-soil map:
+1 2 3
 
-humidity-to-location map:
-60 56 37
-56 93 4",
+soil-to-seed map:
+2 1 3",
         )
         .unwrap();
 
         assert_eq!(
-            almanac.get_type_ranges(SeedRange::new(82, 82), "location"),
-            vec![SeedRange::new(46, 46)]
+            almanac.validate("seed", "location"),
+            vec![AlmanacIssue::CyclicChain {
+                chain: vec![
+                    String::from("seed"),
+                    String::from("soil"),
+                    String::from("seed")
+                ],
+                line: 6,
+            }]
         );
+    }
+
+    #[test]
+    fn test_seed_relation_range_source() {
+        let range = SeedRelationRange::from_str("50 98 2").unwrap();
+
+        // Not in range
+        assert_eq!(range.get_source(&49), None);
+        assert_eq!(range.get_source(&52), None);
+
+        // In range
+        assert_eq!(range.get_source(&50), Some(98));
+        assert_eq!(range.get_source(&51), Some(99));
+    }
+
+    #[test]
+    fn test_seed_relation_table_get_sources() {
+        let table = SeedRelationTable::from_str("seed-to-soil map:\n50 98 2\n52 50 48").unwrap();
+
+        // mapped from a range
+        assert_eq!(table.get_sources(&81), vec![79]);
+        // mapped by a range, and the value itself is not in any range
+        assert_eq!(table.get_sources(&51), vec![99]);
+        // unmapped value
+        assert_eq!(table.get_sources(&10), vec![10]);
+        // 98 maps elsewhere, and nothing maps to it
+        assert_eq!(table.get_sources(&98), vec![96]);
+        assert_eq!(table.get_sources(&100), vec![100]);
+
+        // every source maps back to the value
+        for value in 0..110 {
+            for source in table.get_sources(&value) {
+                assert_eq!(table.get_relation(&source), value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_seed_relation_table_inverse_update_range() {
+        let table = SeedRelationTable::from_str("seed-to-soil map:\n50 98 2\n52 50 48").unwrap();
 
         assert_eq!(
-            almanac.get_type_ranges(SeedRange::new(79, 93), "location"),
-            vec![
-                SeedRange {
-                    start: 46,
-                    num_values: 10,
-                    end: 55
-                },
-                SeedRange {
-                    start: 60,
-                    num_values: 1,
-                    end: 60
-                },
-                SeedRange {
-                    start: 82,
-                    num_values: 3,
-                    end: 84
-                }
-            ]
+            table.inverse_update_range(&set(&[(45, 56)])),
+            set(&[(45, 54), (98, 100)])
         );
 
+        // overlapping sources only use the first range
+        let table = SeedRelationTable::from_str("a-to-b map:\n0 10 5\n100 12 5").unwrap();
         assert_eq!(
-            almanac.get_type_ranges(SeedRange::new(55, 67), "location"),
-            vec![
-                SeedRange {
-                    start: 56,
-                    num_values: 4,
-                    end: 59
-                },
-                SeedRange {
-                    start: 86,
-                    num_values: 4,
-                    end: 89
-                },
-                SeedRange {
-                    start: 94,
-                    num_values: 5,
-                    end: 98
-                }
-            ]
+            table.inverse_update_range(&set(&[(100, 105)])),
+            set(&[(15, 17), (100, 105)])
         );
     }
 
     #[test]
-    fn test_almanac_from_str() {
-        let almanac = Almanac::from_str(
-            "seeds: 79 14 55 13
+    fn test_almanac_get_seed_values() {
+        let almanac = Almanac::from_str(EXAMPLE).unwrap();
 
-seed-to-soil map:
-50 98 2
-52 50 48
+        assert_eq!(almanac.get_source_values(82, "seed", "location"), vec![79]);
+        assert_eq!(almanac.get_source_values(35, "seed", "location"), vec![13]);
+        assert_eq!(almanac.get_source_values(81, "seed", "soil"), vec![79]);
+        assert_eq!(almanac.get_source_values(1, "seed", "unknown"), vec![]);
+    }
 
-soil-to-fertilizer map:
-0 15 37
-37 52 2
-39 0 15
+    #[test]
+    fn test_almanac_get_seed_ranges() {
+        let almanac = Almanac::from_str(EXAMPLE).unwrap();
 
-fertilizer-to-water map:
-49 53 8
-0 11 42
-42 0 7
-57 7 4
+        assert!(almanac
+            .get_source_ranges(set(&[(46, 47)]), "seed", "location")
+            .contains(&82));
 
-water-to-light map:
-88 18 7
-18 25 70
+        // every seed in the ranges maps into the location range
+        for range in almanac
+            .get_source_ranges(set(&[(40, 61)]), "seed", "location")
+            .iter()
+        {
+            for seed in range.start..range.end {
+                let location = almanac.get_type_value(seed, "seed", "location").unwrap();
+                assert!((40..61).contains(&location));
+            }
+        }
+    }
 
-light-to-temperature map:
-45 77 23
-81 45 19
-68 64 13
+    #[test]
+    fn test_almanac_lowest_reachable_value() {
+        let almanac = Almanac::from_str(EXAMPLE).unwrap();
 
-temperature-to-humidity map:
-0 69 1
-1 0 69
+        assert_eq!(
+            almanac.lowest_reachable_value(&set(&[(79, 93), (55, 68)]), "seed", "location"),
+            Some(46)
+        );
+        assert_eq!(
+            almanac.lowest_reachable_value(&set(&[(79, 80)]), "seed", "location"),
+            Some(82)
+        );
 
-humidity-to-location map:
-60 56 37
-56 93 4",
+        // values near the top of the id space are still found
+        let almanac =
+            Almanac::from_str("seeds: 1 1\n\nseed-to-location map:\n18446744073709551600 0 10")
+                .unwrap();
+        assert_eq!(
+            almanac.lowest_reachable_value(&set(&[(1, 2)]), "seed", "location"),
+            Some(18446744073709551601)
+        );
+    }
+
+    #[test]
+    fn test_almanac_trace_seed() {
+        let almanac = Almanac::from_str(EXAMPLE).unwrap();
+
+        assert_eq!(
+            almanac
+                .trace(79, "seed", "location")
+                .iter()
+                .map(|(_, value)| *value)
+                .collect::<Vec<_>>(),
+            vec![79, 81, 81, 81, 74, 78, 78, 82]
+        );
+    }
+
+    #[test]
+    fn test_seed_relation_table_compose() {
+        let seed_to_soil =
+            SeedRelationTable::from_str("seed-to-soil map:\n50 98 2\n52 50 48").unwrap();
+        let soil_to_fertilizer =
+            SeedRelationTable::from_str("soil-to-fertilizer map:\n0 15 37\n37 52 2\n39 0 15")
+                .unwrap();
+
+        let composed = seed_to_soil.compose(&soil_to_fertilizer);
+        assert_eq!(composed.from, "seed");
+        assert_eq!(composed.to, "fertilizer");
+
+        // ranges are sorted and don't overlap
+        assert!(composed
+            .ranges
+            .windows(2)
+            .all(|pair| pair[0].source_start + pair[0].range <= pair[1].source_start));
+
+        for id in 0..200 {
+            assert_eq!(
+                composed.get_relation(&id),
+                soil_to_fertilizer.get_relation(&seed_to_soil.get_relation(&id))
+            );
+        }
+    }
+
+    #[test]
+    fn test_composed_table() {
+        let almanac = Almanac::from_str(EXAMPLE).unwrap();
+        let composed = almanac.compose("seed", "location").unwrap();
+
+        for seed in 0..200 {
+            assert_eq!(
+                composed.get_relation(&seed),
+                almanac.get_type_value(seed, "seed", "location").unwrap()
+            );
+        }
+
+        assert_eq!(
+            composed.update_range(&set(&[(79, 93)])),
+            (79..93)
+                .map(|seed| Interval::with_length(
+                    almanac.get_type_value(seed, "seed", "location").unwrap(),
+                    1
+                ))
+                .collect()
+        );
+
+        // stops before a type that doesn't exist
+        assert_eq!(almanac.compose("seed", "unknown"), None);
+
+        // an identity table leaves everything alone
+        let identity = ComposedTable {
+            table: SeedRelationTable::default(),
+        };
+        assert_eq!(identity.get_relation(&42), 42);
+        assert_eq!(identity.update_range(&set(&[(1, 6)])), set(&[(1, 6)]));
+    }
+
+    #[test]
+    fn test_almanac_category_queries() {
+        let almanac = Almanac::from_str(EXAMPLE).unwrap();
+
+        // seed 79 is soil 81, which ends up as humidity 78
+        assert_eq!(almanac.get_type_value(81, "soil", "humidity"), Some(78));
+        assert_eq!(
+            almanac
+                .compose("soil", "humidity")
+                .unwrap()
+                .get_relation(&81),
+            78
+        );
+        assert_eq!(almanac.find_path("soil", "humidity").unwrap().len(), 5);
+        assert_eq!(almanac.get_source_values(78, "soil", "humidity"), vec![81]);
+
+        // going nowhere leaves the id alone
+        assert_eq!(almanac.find_path("soil", "soil").unwrap().len(), 0);
+        assert_eq!(almanac.get_type_value(81, "soil", "soil"), Some(81));
+
+        // tables only go one way
+        assert!(almanac.find_path("location", "seed").is_none());
+        assert_eq!(almanac.get_type_value(82, "location", "seed"), None);
+    }
+
+    #[test]
+    fn test_almanac_multiple_tables_from_a_category() {
+        let almanac =
+            Almanac::from_str(&format!("{EXAMPLE}\n\nseed-to-humidity map:\n0 0 100")).unwrap();
+
+        assert_eq!(almanac.tables["seed"].len(), 2);
+        // the shortcut is the shortest way to humidity, and on to location
+        assert_eq!(almanac.get_type_value(79, "seed", "humidity"), Some(79));
+        assert_eq!(almanac.get_type_value(79, "seed", "location"), Some(83));
+        // but the long way round is still there
+        assert_eq!(almanac.get_type_value(79, "seed", "light"), Some(74));
+        assert!(almanac.validate("seed", "location").is_empty());
+
+        // a branch that goes nowhere is fine while there's still a way through
+        let almanac =
+            Almanac::from_str(&format!("{EXAMPLE}\n\nsoil-to-color map:\n1 2 3")).unwrap();
+        let issues = almanac.validate("seed", "location");
+        assert_eq!(
+            issues,
+            vec![AlmanacIssue::DeadEndBranch {
+                category: String::from("color"),
+                target: String::from("location"),
+                line: 35,
+            }]
+        );
+        assert_eq!(issues[0].severity(), Severity::Warning);
+
+        // and so is a branch that loops back
+        let almanac = Almanac::from_str(&format!("{EXAMPLE}\n\nsoil-to-seed map:\n1 2 3")).unwrap();
+        let issues = almanac.validate("seed", "location");
+        assert_eq!(
+            issues,
+            vec![AlmanacIssue::LoopingBranch {
+                chain: ["seed", "soil", "seed"].map(String::from).to_vec(),
+                line: 35,
+            }]
+        );
+        assert_eq!(issues[0].severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn test_almanac_to_dot() {
+        let almanac = Almanac::from_str(
+            "seeds: 1 2
+
+seed-to-soil map:
+1 2 3
+4 5 6
+
+seed-to-water map:
+1 2 3",
         )
         .unwrap();
 
-        assert_eq!(almanac.seeds, vec![79, 14, 55, 13]);
-        assert_eq!(almanac.tables.len(), 7); // todo: check all the table values?
+        assert_eq!(
+            almanac.to_dot(),
+            "digraph almanac {
+    \"seed\" -> \"soil\" [label=\"line 3, 2 ranges\"];
+    \"seed\" -> \"water\" [label=\"line 7, 1 ranges\"];
+}
+"
+        );
     }
 }
@@ -1,10 +1,11 @@
 use clap::Parser;
 use nom_supreme::ParserExt;
-use std::{fs::read_to_string, path::PathBuf, process};
+use std::{fs::read_to_string, ops::RangeInclusive, path::PathBuf, process};
 
 use nom::{
     bytes::complete::is_not,
-    character::complete::{self, line_ending, space1},
+    character::complete::{self, digit1, line_ending, space1},
+    combinator::map_res,
     multi::separated_list1,
     sequence::separated_pair,
     IResult, Parser as _,
@@ -22,19 +23,19 @@ fn main() {
     // Read file from CLI arg
     if let Ok(file) = read_to_string(&args.input_file) {
         let (_, races) =
-            BoatRace::parse_races(file.as_str()).expect("input file to parse as races");
+            BoatRace::parse_races(file.as_str(), false).expect("input file to parse as races");
+        let (_, kerned_races) = BoatRace::parse_races(file.as_str(), true)
+            .expect("input file to parse as a kerned race");
 
         let part_1_answer = races
             .iter()
-            .map(|race| race.winning_holds().len())
-            .product::<usize>();
+            .map(|race| race.ways_to_win())
+            .product::<u128>();
 
-        let part_2_answer = BoatRace {
-            time: 58_819_676,
-            distance: 434_104_122_191_218,
-        }
-        .winning_holds()
-        .len();
+        let part_2_answer = kerned_races
+            .iter()
+            .map(|race| race.ways_to_win())
+            .product::<u128>();
 
         println!("Part 1: {}\nPart 2: {}", part_1_answer, part_2_answer);
     } else {
@@ -44,38 +45,114 @@ fn main() {
 }
 
 struct BoatRace {
-    time: u64,
-    distance: u64,
+    time: u128,
+    distance: u128,
+}
+
+/// The largest whole number whose square is no more than `n`
+///
+/// Uses Newton's method, starting from a power of two above the root so every
+/// step moves down towards it.
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    let mut root = 1 << ((u128::BITS - n.leading_zeros()).div_ceil(2));
+    loop {
+        let next = (root + n / root) / 2;
+        if next >= root {
+            return root;
+        }
+        root = next;
+    }
 }
 
 impl BoatRace {
+    /// Check if holding the button for a duration beats the record
+    ///
+    /// Arguments:
+    /// - duration: How long the button is held for
+    fn wins(&self, duration: u128) -> bool {
+        // too far to count is further than any record
+        duration
+            .checked_mul(self.time - duration)
+            .is_none_or(|distance| distance > self.distance)
+    }
+
     /// Calculate the winning holds for the race
-    fn winning_holds(&self) -> Vec<u64> {
-        let mut winning_holds = vec![];
-        let mut duration = 0;
-
-        loop {
-            duration += 1;
-            let distance = duration * (self.time - duration);
-            if distance > self.distance {
-                winning_holds.push(duration);
-            } else if !winning_holds.is_empty() {
-                break;
+    ///
+    /// Holding for `h` goes `h * (time - h)`, so the winning holds lie between the
+    /// roots of `h² - time * h + distance = 0`, which are about `sqrt(best - distance)`
+    /// either side of the best hold. The integer square root can land either side
+    /// of a root, so the bounds are nudged until they're exact.
+    ///
+    /// If no hold wins, the range is empty.
+    fn winning_holds(&self) -> RangeInclusive<u128> {
+        let half = self.time / 2;
+        let mut shortest = match half.checked_mul(self.time - half) {
+            Some(best) if best > self.distance => half - isqrt(best - self.distance).min(half),
+            // no hold beats the record, so start past the best one
+            Some(_) => half + 1,
+            // the best hold goes too far to count, so search for the shortest instead
+            None => {
+                let (mut low, mut high) = (0, half);
+                while low < high {
+                    let middle = low + (high - low) / 2;
+                    if self.wins(middle) {
+                        high = middle;
+                    } else {
+                        low = middle + 1;
+                    }
+                }
+                low
             }
+        };
+        while shortest > 0 && self.wins(shortest - 1) {
+            shortest -= 1;
+        }
+        while shortest <= half && !self.wins(shortest) {
+            shortest += 1;
         }
 
-        winning_holds
+        // the distances are symmetric around half the time
+        shortest..=self.time.saturating_sub(shortest)
     }
 
-    fn parse_races(s: &str) -> IResult<&str, Vec<Self>> {
+    /// The number of different holds that win the race
+    fn ways_to_win(&self) -> u128 {
+        let holds = self.winning_holds();
+        if holds.is_empty() {
+            0
+        } else {
+            holds.end() - holds.start() + 1
+        }
+    }
+
+    /// Parse the races from the sheet of paper
+    ///
+    /// Arguments:
+    /// - s: The times on one line, followed by the distances on the next
+    /// - kerned: Ignore the spaces, so each line holds one big number for one race
+    fn parse_races(s: &str, kerned: bool) -> IResult<&str, Vec<Self>> {
         /// Parse numbers from a string
-        fn nums(input: &str) -> IResult<&str, Vec<u64>> {
+        fn nums(input: &str) -> IResult<&str, Vec<u128>> {
             is_not("0123456789")
-                .precedes(separated_list1(space1, complete::u64))
+                .precedes(separated_list1(space1, complete::u128))
                 .parse(input)
         }
 
-        let (left_overs, (times, distances)) = separated_pair(nums, line_ending, nums).parse(s)?;
+        /// Parse the digits in a string as a single number
+        fn kerned_nums(input: &str) -> IResult<&str, Vec<u128>> {
+            map_res(
+                is_not("0123456789").precedes(separated_list1(space1, digit1)),
+                |digits: Vec<&str>| digits.concat().parse::<u128>().map(|num| vec![num]),
+            )
+            .parse(input)
+        }
+
+        let line = if kerned { kerned_nums } else { nums };
+        let (left_overs, (times, distances)) = separated_pair(line, line_ending, line).parse(s)?;
 
         Ok((
             left_overs,
@@ -93,7 +170,7 @@ impl BoatRace {
 
 #[cfg(test)]
 mod tests_day_06 {
-    use super::BoatRace;
+    use super::{isqrt, BoatRace};
 
     #[test]
     fn test_boat_race_winning_holds() {
@@ -103,7 +180,7 @@ mod tests_day_06 {
                 distance: 9,
             }
             .winning_holds(),
-            (2..=5).collect::<Vec<_>>()
+            2..=5
         );
 
         assert_eq!(
@@ -112,7 +189,7 @@ mod tests_day_06 {
                 distance: 40,
             }
             .winning_holds(),
-            (4..=11).collect::<Vec<_>>()
+            4..=11
         );
 
         assert_eq!(
@@ -121,7 +198,125 @@ mod tests_day_06 {
                 distance: 200,
             }
             .winning_holds(),
-            (11..=19).collect::<Vec<_>>()
+            11..=19
         );
     }
+
+    #[test]
+    fn test_boat_race_ways_to_win() {
+        let race = BoatRace {
+            time: 71530,
+            distance: 940200,
+        };
+        assert_eq!(race.winning_holds(), 14..=71516);
+        assert_eq!(race.ways_to_win(), 71503);
+
+        // matching the record isn't enough
+        let race = BoatRace {
+            time: 10,
+            distance: 25,
+        };
+        assert!(race.winning_holds().is_empty());
+        assert_eq!(race.ways_to_win(), 0);
+        assert_eq!(
+            BoatRace {
+                time: 10,
+                distance: 24,
+            }
+            .winning_holds(),
+            5..=5
+        );
+
+        // agrees with trying every hold
+        for time in 0..60 {
+            for distance in 0..1000 {
+                let race = BoatRace { time, distance };
+                let expected = (0..=time).filter(|hold| race.wins(*hold)).count();
+                assert_eq!(race.ways_to_win(), expected as u128);
+            }
+        }
+    }
+
+    #[test]
+    fn test_boat_race_parse_races() {
+        let input = "Time:      7  15   30\nDistance:  9  40  200";
+
+        let (_, races) = BoatRace::parse_races(input, false).unwrap();
+        assert_eq!(
+            races
+                .iter()
+                .map(|race| (race.time, race.distance))
+                .collect::<Vec<_>>(),
+            vec![(7, 9), (15, 40), (30, 200)]
+        );
+
+        let (_, races) = BoatRace::parse_races(input, true).unwrap();
+        assert_eq!(
+            races
+                .iter()
+                .map(|race| (race.time, race.distance))
+                .collect::<Vec<_>>(),
+            vec![(71530, 940200)]
+        );
+    }
+
+    #[test]
+    fn test_boat_race_large_numbers() {
+        // beyond u64
+        let race = BoatRace {
+            time: 58_819_676_000_000,
+            distance: 434_104_122_191_218_000_000_000_000,
+        };
+        let holds = race.winning_holds();
+        assert!(race.wins(*holds.start()) && !race.wins(holds.start() - 1));
+        assert!(race.wins(*holds.end()) && !race.wins(holds.end() + 1));
+
+        // the best hold goes further than u128 can count
+        let race = BoatRace {
+            time: u128::MAX,
+            distance: u128::MAX - 1,
+        };
+        // holding for 1 only matches the record
+        assert_eq!(race.winning_holds(), 2..=u128::MAX - 2);
+
+        let race = BoatRace {
+            time: 1 << 100,
+            distance: 1 << 120,
+        };
+        let holds = race.winning_holds();
+        assert!(race.wins(*holds.start()) && !race.wins(holds.start() - 1));
+        assert_eq!(*holds.end(), race.time - holds.start());
+
+        // nothing can win
+        let race = BoatRace {
+            time: 10,
+            distance: u128::MAX,
+        };
+        assert!(race.winning_holds().is_empty());
+        assert_eq!(race.ways_to_win(), 0);
+        assert!(BoatRace {
+            time: 0,
+            distance: 0
+        }
+        .winning_holds()
+        .is_empty());
+
+        let (_, races) = BoatRace::parse_races(
+            "Time: 58819676 000000\nDistance: 434104122191218 000000000000",
+            true,
+        )
+        .unwrap();
+        assert_eq!(races[0].time, 58_819_676_000_000);
+        assert_eq!(races[0].distance, 434_104_122_191_218_000_000_000_000);
+    }
+
+    #[test]
+    fn test_isqrt() {
+        for n in 0..10_000 {
+            let root = isqrt(n);
+            assert!(root * root <= n && (root + 1) * (root + 1) > n);
+        }
+        assert_eq!(isqrt(u64::MAX as u128), u32::MAX as u128);
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+    }
 }
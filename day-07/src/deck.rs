@@ -0,0 +1,96 @@
+use crate::hand::Rules;
+
+/// Rules for any deck of cards, for games played with something other than the
+/// standard thirteen
+#[derive(Debug, PartialEq, Eq)]
+pub struct Deck {
+    cards: String,
+    wild_card: Option<char>,
+}
+
+impl Deck {
+    /// Creates a new Deck with no wild card
+    ///
+    /// Arguments:
+    /// - cards: Every card in the deck, from weakest to strongest
+    pub fn new(cards: &str) -> Self {
+        Self {
+            cards: String::from(cards),
+            wild_card: None,
+        }
+    }
+
+    /// Make one of the cards wild, which also makes it the weakest card
+    ///
+    /// Arguments:
+    /// - wild_card: The card to make wild
+    pub fn with_wild_card(self, wild_card: char) -> Self {
+        Self {
+            cards: std::iter::once(wild_card)
+                .chain(self.cards.chars().filter(|card| *card != wild_card))
+                .collect(),
+            wild_card: Some(wild_card),
+        }
+    }
+}
+
+impl Rules for Deck {
+    fn card_order(&self) -> &str {
+        &self.cards
+    }
+
+    fn wild_card(&self) -> Option<char> {
+        self.wild_card
+    }
+}
+
+#[cfg(test)]
+mod tests_day_07_deck {
+    use super::Deck;
+    use crate::hand::{total_winnings, CamelCardHand, CamelCardHandType};
+
+    const EXAMPLE: &str = "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483";
+
+    #[test]
+    fn test_standard_deck() {
+        let deck = Deck::new("23456789TJQKA");
+        let mut hands = EXAMPLE
+            .lines()
+            .flat_map(|line| CamelCardHand::parse(line, &deck))
+            .collect::<Vec<_>>();
+        assert_eq!(total_winnings(&mut hands), 6440);
+
+        let deck = deck.with_wild_card('J');
+        assert_eq!(deck.cards, "J23456789TQKA");
+        let mut hands = EXAMPLE
+            .lines()
+            .flat_map(|line| CamelCardHand::parse(line, &deck))
+            .collect::<Vec<_>>();
+        assert_eq!(total_winnings(&mut hands), 5905);
+    }
+
+    #[test]
+    fn test_bigger_hands_and_decks() {
+        let deck = Deck::new("0123456789abcdef").with_wild_card('0');
+
+        let hand = CamelCardHand::parse("ff0f00a 12", &deck).unwrap();
+        assert_eq!(hand.hand_type, CamelCardHandType::Other(vec![6, 1]));
+
+        let mut hands = ["1234567 1", "ffeedd0 2", "1112223 3", "a0bcdef 4"]
+            .iter()
+            .map(|hand| CamelCardHand::parse(hand, &deck).unwrap())
+            .collect::<Vec<_>>();
+        hands.sort();
+        assert_eq!(
+            hands.iter().map(|hand| hand.bid).collect::<Vec<_>>(),
+            vec![1, 4, 2, 3]
+        );
+
+        // the standard cards aren't in this deck
+        assert!(CamelCardHand::parse("AKQJT98 1", &deck).is_err());
+    }
+}
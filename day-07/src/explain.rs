@@ -0,0 +1,198 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::hand::{total_winnings, CamelCardHand, CamelCardHandType, Rules};
+
+/// How to show why each hand ranked where it did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExplainFormat {
+    /// A table with a row for each hand
+    Table,
+    /// JSON with an object for each hand
+    Json,
+}
+
+/// Why a hand ranked where it did, and what it won
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct HandExplanation {
+    /// Where the hand ranked, where the weakest is 1
+    pub rank: usize,
+    pub cards: String,
+    /// The type of the hand before any wild cards are used
+    pub raw_type: CamelCardHandType,
+    /// The type of the hand after the wild cards are used
+    pub hand_type: CamelCardHandType,
+    /// The card the wild cards pretend to be
+    pub wild_target: Option<char>,
+    pub bid: u32,
+    /// The bid multiplied by the rank
+    pub winnings: u128,
+}
+
+/// Every hand in a game, from weakest to strongest
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Explanation {
+    pub hands: Vec<HandExplanation>,
+    pub total_winnings: u128,
+}
+
+impl Explanation {
+    /// Rank every hand and explain where each one ended up
+    ///
+    /// Arguments:
+    /// - hands: The hands to rank
+    /// - rules: The rules the hands were dealt with
+    pub fn new<R: Rules>(mut hands: Vec<CamelCardHand<R>>, rules: &R) -> Self {
+        let total_winnings = total_winnings(&mut hands);
+        let hands = hands
+            .into_iter()
+            .enumerate()
+            .map(|(index, hand)| HandExplanation {
+                rank: index + 1,
+                raw_type: hand.raw_hand_type(),
+                wild_target: hand.wild_target(rules),
+                winnings: hand.bid as u128 * (index as u128 + 1),
+                cards: hand.cards,
+                hand_type: hand.hand_type,
+                bid: hand.bid,
+            })
+            .collect();
+
+        Self {
+            hands,
+            total_winnings,
+        }
+    }
+
+    /// Show the explanation as a table, with the total at the bottom
+    pub fn to_table(&self) -> String {
+        let header = [
+            "Rank", "Cards", "Raw type", "Type", "Wild as", "Bid", "Winnings",
+        ]
+        .map(String::from);
+        let rows = self.hands.iter().map(|hand| {
+            [
+                hand.rank.to_string(),
+                hand.cards.clone(),
+                format!("{:?}", hand.raw_type),
+                format!("{:?}", hand.hand_type),
+                hand.wild_target.map(String::from).unwrap_or_default(),
+                hand.bid.to_string(),
+                hand.winnings.to_string(),
+            ]
+        });
+        let rows = std::iter::once(header).chain(rows).collect::<Vec<_>>();
+
+        let widths = (0..7)
+            .map(|column| {
+                rows.iter()
+                    .map(|row| row[column].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        // numbers line up on the right, and everything else on the left
+        let numeric = [true, false, false, false, false, true, true];
+
+        let mut output = String::new();
+        for row in &rows {
+            let line = row
+                .iter()
+                .zip(widths.iter().zip(numeric))
+                .map(|(cell, (width, numeric))| match numeric {
+                    true => format!("{cell:>width$}"),
+                    false => format!("{cell:<width$}"),
+                })
+                .collect::<Vec<_>>()
+                .join("  ");
+            output.push_str(line.trim_end());
+            output.push('\n');
+        }
+        output.push_str(&format!("Total winnings: {}", self.total_winnings));
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests_day_07_explain {
+    use super::{Explanation, HandExplanation};
+    use crate::{
+        hand::{CamelCardHand, CamelCardHandType},
+        joker::Joker,
+    };
+
+    const EXAMPLE: &str = "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483";
+
+    fn explain() -> Explanation {
+        let hands = EXAMPLE
+            .lines()
+            .map(|line| CamelCardHand::parse(line, &Joker).unwrap())
+            .collect();
+
+        Explanation::new(hands, &Joker)
+    }
+
+    #[test]
+    fn test_explain() {
+        let explanation = explain();
+
+        assert_eq!(explanation.total_winnings, 5905);
+        assert_eq!(
+            explanation
+                .hands
+                .iter()
+                .map(|hand| hand.cards.as_str())
+                .collect::<Vec<_>>(),
+            vec!["32T3K", "KK677", "T55J5", "QQQJA", "KTJJT"]
+        );
+        assert_eq!(
+            explanation.hands[4],
+            HandExplanation {
+                rank: 5,
+                cards: String::from("KTJJT"),
+                raw_type: CamelCardHandType::TwoPair,
+                hand_type: CamelCardHandType::FourOfAKind,
+                wild_target: Some('T'),
+                bid: 220,
+                winnings: 1100,
+            }
+        );
+        assert_eq!(explanation.hands[0].wild_target, None);
+    }
+
+    #[test]
+    fn test_to_table() {
+        let table = explain().to_table();
+        let mut lines = table.lines();
+
+        assert_eq!(
+            lines.next(),
+            Some("Rank  Cards  Raw type      Type         Wild as  Bid  Winnings")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("   1  32T3K  OnePair       OnePair               765       765")
+        );
+        assert_eq!(
+            lines.nth(3),
+            Some("   5  KTJJT  TwoPair       FourOfAKind  T        220      1100")
+        );
+        assert_eq!(lines.next(), Some("Total winnings: 5905"));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let json = serde_json::to_value(explain()).unwrap();
+
+        assert_eq!(json["total_winnings"], 5905);
+        assert_eq!(json["hands"][4]["raw_type"], "TwoPair");
+        assert_eq!(json["hands"][4]["wild_target"], "T");
+        assert_eq!(json["hands"][0]["wild_target"], serde_json::Value::Null);
+    }
+}
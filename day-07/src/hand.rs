@@ -0,0 +1,544 @@
+use nom::{
+    bytes::complete::is_a,
+    character::complete::{space1, u32},
+    error::{Error, ErrorKind},
+    sequence::separated_pair,
+    Finish, IResult,
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt, marker::PhantomData, str::FromStr};
+
+/// How a game of camel cards is played
+///
+/// Only the deck is required, everything else defaults to the standard game.
+pub trait Rules {
+    /// Every card in the deck, from weakest to strongest
+    fn card_order(&self) -> &str;
+
+    /// The card that can act like whatever card makes the hand strongest, if any
+    fn wild_card(&self) -> Option<char> {
+        None
+    }
+
+    /// Get how strong a card is, where the weakest is 0
+    fn card_rank(&self, card: char) -> Option<u8> {
+        self.card_order()
+            .chars()
+            .position(|c| c == card)
+            .map(|rank| rank as u8)
+    }
+
+    /// Put the ranks of a hand's cards in the order they're compared to break ties
+    ///
+    /// By default the first card is compared first, then the second, and so on.
+    ///
+    /// Arguments:
+    /// - ranks: The rank of each card, in the order they were dealt
+    fn tie_break(&self, ranks: Vec<u8>) -> Vec<u8> {
+        ranks
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum CamelCardHandType {
+    FiveOfAKind,
+    FourOfAKind,
+    FullHouse,
+    ThreeOfAKind,
+    TwoPair,
+    OnePair,
+    High,
+    /// A hand that isn't five cards, by the size of each group of cards
+    Other(Vec<u8>),
+}
+
+impl CamelCardHandType {
+    /// Name the type of hand with the given signature
+    ///
+    /// Arguments:
+    /// - signature: The size of each group of matching cards, biggest first
+    pub fn from_signature(signature: &[u8]) -> Self {
+        match signature {
+            [5] => Self::FiveOfAKind,
+            [4, 1] => Self::FourOfAKind,
+            [3, 2] => Self::FullHouse,
+            [3, 1, 1] => Self::ThreeOfAKind,
+            [2, 2, 1] => Self::TwoPair,
+            [2, 1, 1, 1] => Self::OnePair,
+            [1, 1, 1, 1, 1] => Self::High,
+            other => Self::Other(other.to_vec()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CamelCardHand<R> {
+    pub cards: String,
+    pub bid: u32,
+    pub hand_type: CamelCardHandType,
+    /// The hand's signature in the top bits, and its tie break ranks below
+    pub key: u128,
+    rules: PhantomData<R>,
+}
+
+impl<R> Ord for CamelCardHand<R>
+where
+    R: Eq,
+{
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+impl<R> PartialOrd for CamelCardHand<R>
+where
+    R: Eq,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<R> fmt::Display for CamelCardHand<R> {
+    /// Show the hand the same way it's written in the input
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.cards, self.bid)
+    }
+}
+
+/// The number of bits needed to hold every number up to `n`
+fn bits(n: usize) -> u32 {
+    usize::BITS - n.leading_zeros()
+}
+
+impl<R: Rules> CamelCardHand<R> {
+    /// Creates a new hand, working out its type and sort key
+    ///
+    /// Panics if the hand is too big to fit in a sort key, see `fits_key`.
+    ///
+    /// Arguments:
+    /// - cards: The cards in the hand, as dealt
+    /// - bid: What the hand bids
+    /// - rules: The rules to play by
+    pub fn new(cards: String, bid: u32, rules: &R) -> Self {
+        let signature = Self::signature(cards.as_str(), rules);
+        let hand_type = CamelCardHandType::from_signature(&signature);
+        let tie_break = rules.tie_break(
+            cards
+                .chars()
+                .map(|card| rules.card_rank(card).unwrap_or_default())
+                .collect(),
+        );
+        let key = Self::sort_key(&signature, &tie_break, rules);
+
+        Self {
+            cards,
+            bid,
+            hand_type,
+            key,
+            rules: PhantomData,
+        }
+    }
+
+    /// Get the biggest hand that fits in a sort key, along with the number of
+    /// bits each group size and each rank takes up in the key
+    ///
+    /// Arguments:
+    /// - rules: The rules to play by
+    fn key_layout(rules: &R) -> (usize, u32, u32) {
+        let rank_bits = bits(rules.card_order().chars().count().saturating_sub(1));
+        let fits =
+            |hand_size: usize| hand_size as u32 * (bits(hand_size) + rank_bits) <= u128::BITS;
+        let hand_size = (1..)
+            .take_while(|hand_size| fits(*hand_size))
+            .last()
+            .unwrap_or(0);
+
+        (hand_size, bits(hand_size), rank_bits)
+    }
+
+    /// Checks if a hand with this many cards fits in a sort key
+    ///
+    /// Arguments:
+    /// - hand_size: The number of cards in the hand
+    /// - rules: The rules to play by
+    pub fn fits_key(hand_size: usize, rules: &R) -> bool {
+        hand_size <= Self::key_layout(rules).0
+    }
+
+    /// Pack a hand into a single number, so hands sort by comparing numbers
+    ///
+    /// The signature goes in the top bits and the tie break ranks go below it,
+    /// both padded with zeros to the biggest hand that fits. Every hand in the
+    /// same game shares this layout, whatever its size, so comparing keys is the
+    /// same as comparing signatures and then tie breaks.
+    ///
+    /// Arguments:
+    /// - signature: The size of each group of matching cards, biggest first
+    /// - tie_break: The card ranks, in the order the rules break ties with them
+    /// - rules: The rules to play by
+    fn sort_key(signature: &[u8], tie_break: &[u8], rules: &R) -> u128 {
+        let (hand_size, count_bits, rank_bits) = Self::key_layout(rules);
+        assert!(
+            tie_break.len() <= hand_size,
+            "hand is too big to fit in a sort key"
+        );
+
+        let pad = |values: &[u8]| {
+            values
+                .iter()
+                .copied()
+                .chain(std::iter::repeat(0))
+                .take(hand_size)
+                .collect::<Vec<_>>()
+        };
+        let key = pad(signature)
+            .into_iter()
+            .fold(0, |key, count| (key << count_bits) | count as u128);
+
+        pad(tie_break)
+            .into_iter()
+            .fold(key, |key, rank| (key << rank_bits) | rank as u128)
+    }
+
+    /// Get the size of each group of matching cards, biggest first
+    ///
+    /// Comparing signatures ranks hands of any size: five of a kind is \[5\],
+    /// which beats four of a kind at \[4, 1\], which beats a full house at
+    /// \[3, 2\], and so on.
+    pub fn signature(cards: &str, rules: &R) -> Vec<u8> {
+        let card_groups = Self::card_groups(cards);
+
+        let wild_count = rules
+            .wild_card()
+            .and_then(|wild_card| card_groups.get(&wild_card).copied())
+            .unwrap_or(0);
+        let mut signature = card_groups
+            .into_iter()
+            .filter(|(card, _)| Some(*card) != rules.wild_card())
+            .map(|(_, count)| count)
+            .collect::<Vec<u8>>();
+        signature.sort_by(|a, b| b.cmp(a));
+
+        // wild cards join the biggest group, unless there's nothing else
+        match signature.first_mut() {
+            Some(biggest) => *biggest += wild_count,
+            None if wild_count > 0 => signature.push(wild_count),
+            None => {}
+        }
+
+        signature
+    }
+
+    /// Count how many of each card there are
+    fn card_groups(cards: &str) -> BTreeMap<char, u8> {
+        cards.chars().fold(BTreeMap::new(), |mut map, card| {
+            *(map.entry(card).or_insert(0)) += 1;
+
+            map
+        })
+    }
+
+    /// Get the type the hand would be if no cards were wild
+    pub fn raw_hand_type(&self) -> CamelCardHandType {
+        let mut signature = Self::card_groups(&self.cards)
+            .into_values()
+            .collect::<Vec<_>>();
+        signature.sort_by(|a, b| b.cmp(a));
+
+        CamelCardHandType::from_signature(&signature)
+    }
+
+    /// Get the card the hand's wild cards pretend to be, if it has any
+    ///
+    /// Wild cards join the biggest group of other cards, and when groups are
+    /// tied they join the strongest card. A hand of only wild cards has
+    /// nothing to join, so it stays as it is.
+    ///
+    /// Arguments:
+    /// - rules: The rules the hand was dealt with
+    pub fn wild_target(&self, rules: &R) -> Option<char> {
+        let wild_card = rules.wild_card()?;
+        let card_groups = Self::card_groups(&self.cards);
+        card_groups.get(&wild_card)?;
+
+        card_groups
+            .into_iter()
+            .filter(|(card, _)| *card != wild_card)
+            .max_by_key(|(card, count)| (*count, rules.card_rank(*card)))
+            .map(|(card, _)| card)
+    }
+
+    /// Parse a hand from a hand/bid pairing, using the cards in the rules' deck
+    ///
+    /// Example: "222JJ 123" -> CamelCardHand { hand: "222JJ", bid: 123 }
+    pub fn parse(s: &str, rules: &R) -> Result<Self, Error<String>> {
+        match parse_hand(s, rules.card_order()).finish() {
+            Ok((_remaining, (cards, _))) if !Self::fits_key(cards.chars().count(), rules) => {
+                Err(Error {
+                    input: cards.to_string(),
+                    code: ErrorKind::TooLarge,
+                })
+            }
+            Ok((_remaining, (cards, bid))) => Ok(Self::new(String::from(cards), bid, rules)),
+            Err(Error { input, code }) => Err(Error {
+                input: input.to_string(),
+                code,
+            }),
+        }
+    }
+}
+
+fn parse_hand<'a>(s: &'a str, deck: &str) -> IResult<&'a str, (&'a str, u32)> {
+    separated_pair(is_a(deck), space1, u32)(s)
+}
+
+impl<R: Rules + Default> FromStr for CamelCardHand<R> {
+    type Err = Error<String>;
+
+    /// Parse a hand from a hand/bid pairing
+    ///
+    /// Example: "222JJ 123" -> CamelCardHand { hand: "222JJ", bid: 123 }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, &R::default())
+    }
+}
+
+/// How many hands there need to be before a radix sort beats a comparison sort
+const RADIX_SORT_THRESHOLD: usize = 1 << 16;
+
+/// Sort hands from weakest to strongest, one byte of their keys at a time
+///
+/// Starting from the lowest byte, each pass deals the hands into a bucket per
+/// byte value, keeping their order within a bucket, and then gathers them up.
+///
+/// Arguments:
+/// - hands: The hands to sort
+pub fn radix_sort<R>(hands: &mut Vec<CamelCardHand<R>>) {
+    let biggest = hands.iter().map(|hand| hand.key).max().unwrap_or(0);
+    let passes = (u128::BITS - biggest.leading_zeros()).div_ceil(8);
+    let mut buckets: Vec<Vec<CamelCardHand<R>>> = (0..256).map(|_| vec![]).collect();
+
+    for pass in 0..passes {
+        for hand in hands.drain(..) {
+            buckets[(hand.key >> (pass * 8)) as usize & 0xff].push(hand);
+        }
+        for bucket in &mut buckets {
+            hands.append(bucket);
+        }
+    }
+}
+
+/// Rank every hand from weakest to strongest, and add up what each one wins
+///
+/// Arguments:
+/// - hands: The hands to rank, which end up sorted
+pub fn total_winnings<R>(hands: &mut Vec<CamelCardHand<R>>) -> u128 {
+    if hands.len() >= RADIX_SORT_THRESHOLD {
+        radix_sort(hands);
+    } else {
+        hands.sort_unstable_by_key(|hand| hand.key);
+    }
+
+    hands
+        .iter()
+        .enumerate()
+        .map(|(index, hand)| hand.bid as u128 * (index as u128 + 1))
+        .sum::<u128>()
+}
+
+#[cfg(test)]
+mod tests_day_07_hand {
+    use std::cmp::Ordering;
+
+    use nom::error::ErrorKind;
+
+    use super::{radix_sort, total_winnings, CamelCardHand, CamelCardHandType, Rules};
+
+    /// Aces are wild, and the weakest card
+    #[derive(Debug, Default, PartialEq, Eq)]
+    struct AcesWild;
+
+    impl Rules for AcesWild {
+        fn card_order(&self) -> &str {
+            "A23456789TJQK"
+        }
+
+        fn wild_card(&self) -> Option<char> {
+            Some('A')
+        }
+    }
+
+    /// Ties are broken by the strongest cards, wherever they are in the hand
+    #[derive(Debug, Default, PartialEq, Eq)]
+    struct HighestFirst;
+
+    impl Rules for HighestFirst {
+        fn card_order(&self) -> &str {
+            "23456789TJQKA"
+        }
+
+        fn tie_break(&self, mut ranks: Vec<u8>) -> Vec<u8> {
+            ranks.sort_by(|a, b| b.cmp(a));
+            ranks
+        }
+    }
+
+    #[test]
+    fn test_aces_wild() {
+        assert_eq!(
+            CamelCardHand::new(String::from("AAKQJ"), 0, &AcesWild).hand_type,
+            CamelCardHandType::ThreeOfAKind
+        );
+        assert_eq!(
+            CamelCardHand::new(String::from("AKKQQ"), 0, &AcesWild).hand_type,
+            CamelCardHandType::FullHouse
+        );
+        assert_eq!(
+            CamelCardHand::new(String::from("JJJJJ"), 0, &AcesWild).hand_type,
+            CamelCardHandType::FiveOfAKind
+        );
+
+        // an ace is the weakest card when breaking ties
+        assert_eq!(
+            CamelCardHand::new(String::from("AKKK2"), 1, &AcesWild).cmp(&CamelCardHand::new(
+                String::from("2KKKA"),
+                1,
+                &AcesWild
+            )),
+            Ordering::Less
+        );
+
+        // tied groups are joined by the strongest card
+        let hand = CamelCardHand::new(String::from("AKQ23"), 1, &AcesWild);
+        assert_eq!(hand.raw_hand_type(), CamelCardHandType::High);
+        assert_eq!(hand.hand_type, CamelCardHandType::OnePair);
+        assert_eq!(hand.wild_target(&AcesWild), Some('K'));
+        let hand = CamelCardHand::new(String::from("AAAAA"), 1, &AcesWild);
+        assert_eq!(hand.wild_target(&AcesWild), None);
+
+        // only cards in the deck can be dealt
+        assert!(CamelCardHand::parse("AAKQJ 1", &AcesWild).is_ok());
+        assert!(CamelCardHand::parse("XAKQJ 1", &AcesWild).is_err());
+    }
+
+    #[test]
+    fn test_signature() {
+        assert_eq!(
+            CamelCardHand::signature("QJJQ2", &HighestFirst),
+            vec![2, 2, 1]
+        );
+        assert_eq!(CamelCardHand::signature("QJJQ2", &AcesWild), vec![2, 2, 1]);
+        assert_eq!(CamelCardHand::signature("QAAQ2", &AcesWild), vec![4, 1]);
+        assert_eq!(CamelCardHand::signature("AAA", &AcesWild), vec![3]);
+        assert!(CamelCardHand::signature("", &AcesWild).is_empty());
+
+        // hands of other sizes
+        assert_eq!(
+            CamelCardHand::new(String::from("KKKQQQ"), 0, &HighestFirst).hand_type,
+            CamelCardHandType::Other(vec![3, 3])
+        );
+        assert_eq!(
+            CamelCardHand::new(String::from("KKA"), 0, &AcesWild).hand_type,
+            CamelCardHandType::Other(vec![3])
+        );
+
+        // bigger groups come first, then more of them
+        let mut hands = [
+            "2345678 1",
+            "2234567 2",
+            "2223456 3",
+            "2233445 4",
+            "2222333 5",
+        ]
+        .iter()
+        .map(|hand| CamelCardHand::parse(hand, &HighestFirst).unwrap())
+        .collect::<Vec<_>>();
+        hands.sort();
+        assert_eq!(
+            hands.iter().map(|hand| hand.bid).collect::<Vec<_>>(),
+            vec![1, 2, 4, 3, 5]
+        );
+    }
+
+    #[test]
+    fn test_tie_break() {
+        // in order the kings would win, but the pair of aces is stronger
+        assert_eq!(
+            CamelCardHand::<HighestFirst>::new(String::from("KKQQ2"), 1, &HighestFirst)
+                .cmp(&CamelCardHand::new(String::from("22AAK"), 1, &HighestFirst)),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn test_sort_key() {
+        // up to fifteen cards fit, with four bits per group and four per rank
+        let hand = CamelCardHand::new(String::from("32T3K"), 1, &HighestFirst);
+        assert_eq!(hand.key, 0x211100000000000_b81100000000000);
+
+        // hands of different sizes still compare by signature first
+        let four = CamelCardHand::new(String::from("AAAA"), 1, &HighestFirst);
+        let five = CamelCardHand::new(String::from("23456"), 2, &HighestFirst);
+        assert!(four > five);
+        let mut hands = vec![four, five];
+        // the high card bids 2 and ranks first, the four of a kind bids 1 and ranks second
+        assert_eq!(total_winnings(&mut hands), 4);
+        assert_eq!(hands[1].cards, "AAAA");
+
+        // a hand too big for the key can't be parsed
+        let cards = "A".repeat(20);
+        assert_eq!(
+            CamelCardHand::parse(format!("{cards} 1").as_str(), &HighestFirst)
+                .unwrap_err()
+                .code,
+            ErrorKind::TooLarge
+        );
+        assert!(CamelCardHand::<HighestFirst>::fits_key(15, &HighestFirst));
+        assert!(!CamelCardHand::<HighestFirst>::fits_key(16, &HighestFirst));
+    }
+
+    #[test]
+    fn test_total_winnings_of_many_hands() {
+        let mut hands = (0..100_000)
+            .map(|_| CamelCardHand::new(String::from("23456"), 1000, &HighestFirst))
+            .collect::<Vec<_>>();
+
+        assert_eq!(total_winnings(&mut hands), 1000 * 100_000 * 100_001 / 2);
+    }
+
+    #[test]
+    fn test_radix_sort() {
+        let deck = "A23456789TJQK";
+        let mut seed: u64 = 2023;
+        let mut random = move |n: usize| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (seed >> 33) as usize % n
+        };
+        let mut hands = (0..5000)
+            .map(|bid| {
+                let cards = (0..5)
+                    .map(|_| deck.as_bytes()[random(deck.len())] as char)
+                    .collect();
+                CamelCardHand::new(cards, bid, &AcesWild)
+            })
+            .collect::<Vec<_>>();
+
+        let mut sorted = hands
+            .iter()
+            .map(|hand| (hand.key, hand.bid))
+            .collect::<Vec<_>>();
+        sorted.sort();
+        radix_sort(&mut hands);
+        assert!(hands.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(
+            hands.iter().map(|hand| hand.key).collect::<Vec<_>>(),
+            sorted.iter().map(|(key, _)| *key).collect::<Vec<_>>()
+        );
+
+        // hands with the same key keep their order
+        assert!(hands
+            .windows(2)
+            .all(|pair| pair[0].key < pair[1].key || pair[0].bid < pair[1].bid));
+    }
+}
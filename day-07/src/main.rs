@@ -1,47 +1,121 @@
 use clap::Parser;
-use std::{fs::read_to_string, path::PathBuf, process, str::FromStr};
+use std::{fs::read_to_string, path::PathBuf, process};
 
+mod deck;
+mod explain;
+mod hand;
 mod joker;
 mod normal;
 
+use deck::Deck;
+use explain::{ExplainFormat, Explanation};
+use hand::{total_winnings, CamelCardHand, Rules};
+use serde::Serialize;
+
 #[derive(clap::Parser)]
 struct Cli {
     input_file: PathBuf,
+    /// Play with a different deck, listing every card from weakest to strongest
+    #[arg(long)]
+    deck: Option<String>,
+    /// The card that is wild in part 2, when playing with a different deck
+    #[arg(long, default_value_t = 'J', requires = "deck")]
+    wild: char,
+    /// Show why each hand ranked where it did, instead of just the answers
+    #[arg(long, value_enum)]
+    explain: Option<ExplainFormat>,
 }
 
-fn main() {
-    // Get command line arguments
-    let args = Cli::parse();
-
-    // Read file from CLI arg
-    if let Ok(file) = read_to_string(&args.input_file) {
-        let mut normal_cards = file
-            .lines()
-            .flat_map(normal::CamelCardHand::from_str)
-            .collect::<Vec<normal::CamelCardHand>>();
+/// Both parts of the explanation, for printing as JSON
+#[derive(Serialize)]
+struct Report {
+    part_1: Explanation,
+    part_2: Explanation,
+}
 
-        normal_cards.sort();
+/// Parse every hand in the file with the given rules
+///
+/// A hand that doesn't parse would quietly change the answer, so the line and
+/// the error are reported and the program exits instead.
+///
+/// Arguments:
+/// - file: The hands and bids, one per line
+/// - rules: The rules to play by
+fn hands<R: Rules>(file: &str, rules: &R) -> Vec<CamelCardHand<R>> {
+    let hands = file
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_num, line)| {
+            CamelCardHand::parse(line, rules).map_err(|error| (line_num + 1, error))
+        })
+        .collect::<Result<Vec<_>, _>>();
 
-        let part_1_answer = normal_cards
-            .iter()
-            .enumerate()
-            .map(|(index, hand)| hand.bid * (index as u32 + 1))
-            .sum::<u32>();
+    match hands {
+        Ok(hands) => hands,
+        Err((line_num, error)) => {
+            eprintln!("Line {}: {}", line_num, error);
+            process::exit(1);
+        }
+    }
+}
 
-        let mut joker_cards = file
-            .lines()
-            .flat_map(joker::CamelCardHand::from_str)
-            .collect::<Vec<joker::CamelCardHand>>();
+/// Play both parts, and print either the answers or an explanation of them
+///
+/// Arguments:
+/// - file: The hands and bids, one per line
+/// - part_1: The rules to play part 1 by
+/// - part_2: The rules to play part 2 by
+/// - explain: How to explain the rankings, if at all
+fn play<P1: Rules, P2: Rules>(
+    file: &str,
+    part_1: &P1,
+    part_2: &P2,
+    explain: Option<ExplainFormat>,
+) {
+    let Some(format) = explain else {
+        let part_1_answer = total_winnings(&mut hands(file, part_1));
+        let part_2_answer = total_winnings(&mut hands(file, part_2));
+        println!("Part 1: {}\nPart 2: {}", part_1_answer, part_2_answer);
+        return;
+    };
 
-        joker_cards.sort();
+    let report = Report {
+        part_1: Explanation::new(hands(file, part_1), part_1),
+        part_2: Explanation::new(hands(file, part_2), part_2),
+    };
+    match format {
+        ExplainFormat::Table => println!(
+            "Part 1:\n{}\n\nPart 2:\n{}",
+            report.part_1.to_table(),
+            report.part_2.to_table()
+        ),
+        ExplainFormat::Json => match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{json}"),
+            Err(error) => {
+                eprintln!("Could not write explanation: {error}");
+                process::exit(1);
+            }
+        },
+    }
+}
 
-        let part_2_answer = joker_cards
-            .iter()
-            .enumerate()
-            .map(|(index, hand)| hand.bid * (index as u32 + 1))
-            .sum::<u32>();
+fn main() {
+    // Get command line arguments
+    let args = Cli::parse();
 
-        println!("Part 1: {}\nPart 2: {}", part_1_answer, part_2_answer);
+    // Read file from CLI arg
+    if let Ok(file) = read_to_string(&args.input_file) {
+        if let Some(deck) = &args.deck {
+            play(
+                &file,
+                &Deck::new(deck),
+                &Deck::new(deck).with_wild_card(args.wild),
+                args.explain,
+            );
+        } else {
+            play(&file, &normal::Normal, &joker::Joker, args.explain);
+        }
     } else {
         eprintln!("Could not read file: {}", args.input_file.display());
         process::exit(1);
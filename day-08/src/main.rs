@@ -2,14 +2,20 @@ use clap::Parser;
 use nom::{
     bytes::complete::tag,
     character::complete::{alphanumeric1, char},
-    error::Error,
+    error::{Error, ErrorKind},
     sequence::{preceded, separated_pair, terminated},
     Finish, IResult,
 };
-use num::integer::lcm;
+use num::Integer;
 use std::{
-    collections::BTreeMap, fmt::Debug, fs::read_to_string, path::PathBuf, process, str::FromStr,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Debug,
+    fs::read_to_string,
+    path::PathBuf,
+    process,
+    str::FromStr,
 };
+use thiserror::Error;
 
 #[derive(clap::Parser)]
 struct Cli {
@@ -25,17 +31,21 @@ fn main() {
         let haunted_wasteland =
             HauntedWasteland::from_str(file.as_str()).expect("Haunted Wasteland to be formatted");
 
-        let part_1_answer = haunted_wasteland.turns("AAA", "ZZZ").len();
-
-        let part_2_answer = haunted_wasteland
-            .map
-            .nodes
-            .keys()
-            .filter(|key| key.ends_with('A'))
-            .map(|node| haunted_wasteland.turns_ends_with(node, 'Z').len())
-            .fold(1, lcm);
+        match haunted_wasteland.walk_to("AAA", &["ZZZ"], false) {
+            Ok(walk) => println!("Part 1: {}", walk.steps),
+            Err(error) => {
+                eprintln!("Part 1: {}", error);
+                process::exit(1);
+            }
+        }
 
-        println!("Part 1: {}\nPart 2: {}", part_1_answer, part_2_answer);
+        match haunted_wasteland.ghost_steps('A', 'Z') {
+            Ok(part_2_answer) => println!("Part 2: {}", part_2_answer),
+            Err(error) => {
+                eprintln!("Part 2: {}", error);
+                process::exit(1);
+            }
+        }
     } else {
         eprintln!("Could not read file: {}", args.input_file.display());
         process::exit(1);
@@ -82,50 +92,133 @@ impl FromStr for HauntedNode {
     }
 }
 
+/// Every node on the map, with their ids interned to indexes
+///
+/// Every id is given an index, whether it has its own line or is only ever
+/// stepped to, so walking the map is just indexing into `transitions`.
 #[derive(Debug, PartialEq, Eq)]
-
 struct HauntedMap {
-    nodes: BTreeMap<String, HauntedNode>,
+    /// The id of each node, by index
+    names: Vec<String>,
+    /// The index of each node, by id
+    indexes: BTreeMap<String, usize>,
+    /// The nodes to the left and right of each node, by index, if it's on the map
+    transitions: Vec<Option<[usize; 2]>>,
 }
 
+impl HauntedMap {
+    /// Get the index of a node, adding it to the map if it's not there yet
+    fn intern(&mut self, name: &str) -> usize {
+        if let Some(index) = self.indexes.get(name) {
+            return *index;
+        }
+
+        let index = self.names.len();
+        self.names.push(String::from(name));
+        self.indexes.insert(String::from(name), index);
+        self.transitions.push(None);
+
+        index
+    }
+
+    /// Add a node and where it leads to the map
+    fn insert(&mut self, node: &HauntedNode) {
+        let index = self.intern(&node.id);
+        let left = self.intern(&node.left);
+        let right = self.intern(&node.right);
+        self.transitions[index] = Some([left, right]);
+    }
+
+    /// Get the index of a node
+    fn index(&self, name: &str) -> Option<usize> {
+        self.indexes.get(name).copied()
+    }
+
+    /// Get every node that has its own line on the map, in order of their ids
+    fn nodes(&self) -> impl Iterator<Item = &str> {
+        self.indexes
+            .iter()
+            .filter(|(_, index)| self.transitions[**index].is_some())
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Take a step from a node, if that node is on the map
+    ///
+    /// Arguments:
+    /// - index: The index of the node to step from
+    /// - direction: The way to turn, 0 for left and 1 for right
+    fn step(&self, index: usize, direction: u8) -> Option<usize> {
+        self.transitions[index].map(|next| next[direction as usize])
+    }
+}
+
+impl FromIterator<HauntedNode> for HauntedMap {
+    fn from_iter<T: IntoIterator<Item = HauntedNode>>(iter: T) -> Self {
+        let mut map = Self {
+            names: Vec::new(),
+            indexes: BTreeMap::new(),
+            transitions: Vec::new(),
+        };
+        for node in iter {
+            map.insert(&node);
+        }
+
+        map
+    }
+}
+
+/// The directions to follow, one byte per turn, where 0 is left and 1 is right
 #[derive(Debug, PartialEq, Eq)]
-struct HauntedDirections(String);
+struct HauntedDirections(Vec<u8>);
 
 impl HauntedDirections {
-    fn to_iter(&self) -> HauntedDirectionsIterator {
+    fn to_iter(&self) -> HauntedDirectionsIterator<'_> {
         HauntedDirectionsIterator {
-            directions: self.0.as_str(),
+            directions: self.0.as_slice(),
             index: 0,
         }
     }
 }
 
+impl FromStr for HauntedDirections {
+    type Err = String;
+
+    /// Parse the directions from a line of Ls and Rs
+    ///
+    /// Example: "LRL" -> HauntedDirections([0, 1, 0])
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars()
+            .map(|c| HauntedDirection::from_char(c).map(|direction| direction as u8))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Repeats the directions forever, or yields nothing if there are none
 struct HauntedDirectionsIterator<'a> {
-    directions: &'a str,
+    directions: &'a [u8],
     index: usize,
 }
 
-impl<'a> Iterator for HauntedDirectionsIterator<'a> {
-    type Item = HauntedDirection;
+impl Iterator for HauntedDirectionsIterator<'_> {
+    type Item = u8;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.index >= self.directions.len() {
             self.index = 0;
         }
 
-        if let Some(char) = self.directions.chars().nth(self.index) {
-            self.index += 1;
-            Some(HauntedDirection::from_char(char).expect("char to be direction"))
-        } else {
-            None
-        }
+        let direction = self.directions.get(self.index).copied();
+        self.index += 1;
+
+        direction
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 enum HauntedDirection {
-    Left,
-    Right,
+    Left = 0,
+    Right = 1,
 }
 
 impl HauntedDirection {
@@ -145,69 +238,284 @@ struct HauntedWasteland {
 }
 
 impl HauntedWasteland {
-    fn turns(&self, from: &str, target: &str) -> Vec<char> {
-        let mut turns = Vec::new();
-
-        let mut current = self.map.nodes.get(from);
-        for turn in self.directions.to_iter() {
-            match turn {
-                HauntedDirection::Left => {
-                    turns.push('L');
-                    if let Some(c) = current {
-                        if c.left == target {
-                            return turns;
-                        } else {
-                            current = self.map.nodes.get(&c.left);
-                        }
-                    }
-                }
-                HauntedDirection::Right => {
-                    turns.push('R');
-                    if let Some(c) = current {
-                        if c.right == target {
-                            return turns;
-                        } else {
-                            current = self.map.nodes.get(&c.right);
-                        }
-                    }
-                }
+    /// Follow the directions from one node until stepping onto any of the targets
+    ///
+    /// Arguments:
+    /// - from: The id of the node to start at
+    /// - targets: The ids of the nodes to finish at
+    /// - record_path: Whether to keep the id of every node walked through
+    fn walk_to(
+        &self,
+        from: &str,
+        targets: &[&str],
+        record_path: bool,
+    ) -> Result<Walk, HauntedError> {
+        let targets = targets
+            .iter()
+            .flat_map(|target| self.map.index(target))
+            .collect::<BTreeSet<_>>();
+
+        self.walk_until(from, |index| targets.contains(&index), record_path)
+    }
+
+    /// Follow the directions from one node until stepping onto a node whose id
+    /// passes the test
+    ///
+    /// Arguments:
+    /// - from: The id of the node to start at
+    /// - is_target: Whether a node, by id, is a target
+    /// - record_path: Whether to keep the id of every node walked through
+    fn walk(
+        &self,
+        from: &str,
+        is_target: impl Fn(&str) -> bool,
+        record_path: bool,
+    ) -> Result<Walk, HauntedError> {
+        self.walk_until(from, |index| is_target(&self.map.names[index]), record_path)
+    }
+
+    /// Follow the directions from one node until stepping onto a node that
+    /// passes the test
+    ///
+    /// There are only so many states the walk can be in, a node and a point in
+    /// the directions, so a walk that takes more steps than that has repeated a
+    /// state and will go round the same loop forever without finding a target.
+    fn walk_until(
+        &self,
+        from: &str,
+        is_target: impl Fn(usize) -> bool,
+        record_path: bool,
+    ) -> Result<Walk, HauntedError> {
+        if self.directions.0.is_empty() {
+            return Err(HauntedError::NoDirections);
+        }
+        let states = self.map.names.len() * self.directions.0.len();
+
+        let mut current = self
+            .map
+            .index(from)
+            .ok_or_else(|| HauntedError::MissingNode(String::from(from)))?;
+        let mut path = record_path.then(|| vec![String::from(from)]);
+        for (steps, direction) in self.directions.to_iter().enumerate() {
+            if steps >= states {
+                return Err(HauntedError::RepeatedState(String::from(from)));
+            }
+
+            current = self
+                .map
+                .step(current, direction)
+                .ok_or_else(|| HauntedError::MissingNode(self.map.names[current].clone()))?;
+            if let Some(path) = path.as_mut() {
+                path.push(self.map.names[current].clone());
+            }
+            if is_target(current) {
+                return Ok(Walk {
+                    steps: steps + 1,
+                    path,
+                });
             }
         }
 
-        turns
+        unreachable!("the directions repeat forever")
     }
 
-    fn turns_ends_with(&self, from: &str, target: char) -> Vec<char> {
-        let mut turns = Vec::new();
-
-        let mut current = self.map.nodes.get(from);
-        for turn in self.directions.to_iter() {
-            match turn {
-                HauntedDirection::Left => {
-                    turns.push('L');
-                    if let Some(c) = current {
-                        if c.left.ends_with(target) {
-                            return turns;
-                        } else {
-                            current = self.map.nodes.get(&c.left);
-                        }
-                    }
-                }
-                HauntedDirection::Right => {
-                    turns.push('R');
-                    if let Some(c) = current {
-                        if c.right.ends_with(target) {
-                            return turns;
-                        } else {
-                            current = self.map.nodes.get(&c.right);
-                        }
+    /// Follow the directions from one node until the walk starts repeating
+    ///
+    /// The walk is in the same state whenever it's on the same node at the same
+    /// point in the directions, and from then on it goes round the same loop
+    /// forever.
+    ///
+    /// Arguments:
+    /// - from: The id of the node to start at
+    /// - is_target: Whether a node, by index, is a target
+    fn cycle(
+        &self,
+        from: &str,
+        is_target: impl Fn(usize) -> bool,
+    ) -> Result<GhostCycle, HauntedError> {
+        let directions = &self.directions.0;
+        if directions.is_empty() {
+            return Err(HauntedError::NoDirections);
+        }
+
+        let mut seen = HashMap::new();
+        let mut hits = Vec::new();
+        let mut current = self
+            .map
+            .index(from)
+            .ok_or_else(|| HauntedError::MissingNode(String::from(from)))?;
+        for step in 0.. {
+            if let Some(first_seen) = seen.insert((current, step % directions.len()), step) {
+                return Ok(GhostCycle {
+                    tail: first_seen,
+                    length: step - first_seen,
+                    tail_hits: hits
+                        .iter()
+                        .copied()
+                        .filter(|hit| (1..first_seen).contains(hit))
+                        .collect(),
+                    cycle_hits: hits.into_iter().filter(|hit| *hit >= first_seen).collect(),
+                });
+            }
+
+            if is_target(current) {
+                hits.push(step);
+            }
+            current = self
+                .map
+                .step(current, directions[step % directions.len()])
+                .ok_or_else(|| HauntedError::MissingNode(self.map.names[current].clone()))?;
+        }
+
+        unreachable!("the walk repeats within one step per state")
+    }
+
+    /// Walk a ghost from every node ending with the start character at once,
+    /// and count the steps until they're all on nodes ending with the target
+    ///
+    /// Each ghost's walk is a tail followed by a loop, so once every ghost is
+    /// in its loop the steps they're all on a target are solved with the
+    /// Chinese remainder theorem, which allows loops that aren't coprime.
+    ///
+    /// Arguments:
+    /// - start: The last character of the ids to start at
+    /// - target: The last character of the ids to finish at
+    fn ghost_steps(&self, start: char, target: char) -> Result<u128, HauntedError> {
+        let is_target = |index: usize| self.map.names[index].ends_with(target);
+        let cycles = self
+            .map
+            .nodes()
+            .filter(|node| node.ends_with(start))
+            .map(|node| {
+                // a ghost that can never reach a target is named in the error
+                self.walk(node, |id| id.ends_with(target), false)?;
+                self.cycle(node, is_target)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // before every ghost is in its loop, the answer has to be one of the
+        // steps a ghost hit a target on its way there
+        if let Some(step) = cycles
+            .iter()
+            .flat_map(|cycle| cycle.tail_hits.iter().copied())
+            .filter(|step| cycles.iter().all(|cycle| cycle.hits(*step)))
+            .min()
+        {
+            return Ok(step as u128);
+        }
+
+        let settled = cycles
+            .iter()
+            .map(|cycle| cycle.tail)
+            .max()
+            .unwrap_or(0)
+            .max(1) as u128;
+        let mut congruences = vec![(0, 1)];
+        for cycle in &cycles {
+            let length = cycle.length as u128;
+            let mut combined = Vec::new();
+            for congruence in &congruences {
+                for hit in &cycle.cycle_hits {
+                    if let Some(congruence) = crt(*congruence, (*hit as u128 % length, length))? {
+                        combined.push(congruence);
                     }
                 }
             }
+            combined.sort();
+            combined.dedup();
+            congruences = combined;
         }
 
-        turns
+        congruences
+            .into_iter()
+            .map(|(residue, modulus)| match residue.cmp(&settled) {
+                std::cmp::Ordering::Less => (settled - residue)
+                    .div_ceil(modulus)
+                    .checked_mul(modulus)
+                    .and_then(|offset| offset.checked_add(residue))
+                    .ok_or(HauntedError::Overflow),
+                _ => Ok(residue),
+            })
+            .min_by_key(|steps| steps.as_ref().ok().copied().unwrap_or(u128::MAX))
+            .unwrap_or(Err(HauntedError::NoSolution))
+    }
+}
+
+/// Solve a pair of congruences, finding every number that satisfies both
+///
+/// Returns None if no number does, which happens when the moduli share a
+/// factor that the residues disagree on.
+///
+/// Arguments:
+/// - first: A residue and modulus, where the residue is below the modulus
+/// - second: Another residue and modulus, where the residue is below the modulus
+fn crt(
+    (a1, m1): (u128, u128),
+    (a2, m2): (u128, u128),
+) -> Result<Option<(u128, u128)>, HauntedError> {
+    let gcd = m1.gcd(&m2);
+    let difference = (a2 + m2 - a1 % m2) % m2;
+    if difference % gcd != 0 {
+        return Ok(None);
     }
+
+    // solve m1 * k = difference (mod m2), dividing through by the gcd first
+    let m2_gcd = m2 / gcd;
+    let inverse = ((m1 / gcd % m2_gcd) as i128)
+        .extended_gcd(&(m2_gcd as i128))
+        .x
+        .rem_euclid(m2_gcd as i128) as u128;
+    let k = difference / gcd % m2_gcd * inverse % m2_gcd;
+
+    let modulus = m1.checked_mul(m2_gcd).ok_or(HauntedError::Overflow)?;
+    Ok(Some((a1 + m1 * k, modulus)))
+}
+
+/// How a walk reached its target
+#[derive(Debug, PartialEq, Eq)]
+struct Walk {
+    steps: usize,
+    /// The id of every node walked through, from the start to the target
+    path: Option<Vec<String>>,
+}
+
+/// A ghost's walk, which always ends up going round the same loop forever
+#[derive(Debug, PartialEq, Eq)]
+struct GhostCycle {
+    /// How many steps it takes to reach the loop
+    tail: usize,
+    /// How many steps it takes to go round the loop
+    length: usize,
+    /// The steps on a target before reaching the loop
+    tail_hits: Vec<usize>,
+    /// The steps on a target the first time round the loop
+    cycle_hits: Vec<usize>,
+}
+
+impl GhostCycle {
+    /// Check if the ghost is on a target after some number of steps
+    fn hits(&self, step: usize) -> bool {
+        if step < self.tail {
+            self.tail_hits.contains(&step)
+        } else {
+            let offset = self.tail + (step - self.tail) % self.length;
+            self.cycle_hits.contains(&offset)
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+enum HauntedError {
+    #[error("Unable to find node {0} on the map")]
+    MissingNode(String),
+    #[error("Unable to reach a target from {0}, the walk goes round in circles")]
+    RepeatedState(String),
+    #[error("Unable to walk without any directions")]
+    NoDirections,
+    #[error("Unable to find a step where every ghost is on a target")]
+    NoSolution,
+    #[error("Unable to count that many steps")]
+    Overflow,
 }
 
 impl FromStr for HauntedWasteland {
@@ -216,19 +524,18 @@ impl FromStr for HauntedWasteland {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut lines = s.lines();
         // first line directions
-        let directions = HauntedDirections(String::from(lines.next().unwrap()));
+        let first_line = lines.next().unwrap_or_default();
+        let directions = HauntedDirections::from_str(first_line).map_err(|_| Error {
+            input: first_line.to_string(),
+            code: ErrorKind::Char,
+        })?;
 
         // skip blank line
         lines.next();
 
-        let map = HauntedMap {
-            nodes: lines.fold(BTreeMap::new(), |mut map, line| {
-                let node = HauntedNode::from_str(line).expect("line to be node");
-                map.insert(node.id.clone(), node);
-
-                map
-            }),
-        };
+        let map = lines
+            .map(HauntedNode::from_str)
+            .collect::<Result<HauntedMap, _>>()?;
 
         Ok(Self { map, directions })
     }
@@ -238,9 +545,21 @@ impl FromStr for HauntedWasteland {
 mod tests_day_08 {
     use std::{collections::BTreeMap, str::FromStr};
 
-    use num::integer::lcm;
+    use super::{
+        crt, GhostCycle, HauntedDirections, HauntedError, HauntedMap, HauntedNode,
+        HauntedWasteland, Walk,
+    };
 
-    use super::{HauntedDirections, HauntedMap, HauntedNode, HauntedWasteland};
+    #[test]
+    fn test_haunted_directions() {
+        let directions = HauntedDirections::from_str("LRR").unwrap();
+        assert_eq!(directions, HauntedDirections(vec![0, 1, 1]));
+        assert_eq!(
+            directions.to_iter().take(7).collect::<Vec<_>>(),
+            vec![0, 1, 1, 0, 1, 1, 0]
+        );
+        assert_eq!(HauntedDirections(vec![]).to_iter().next(), None);
+    }
 
     #[test]
     fn test_haunted_node_from_str() {
@@ -287,45 +606,40 @@ GGG = (GGG, GGG)
 ZZZ = (ZZZ, ZZZ)"
             ),
             Ok(HauntedWasteland {
-                directions: HauntedDirections(String::from("RL")),
+                directions: HauntedDirections(vec![1, 0]),
                 map: HauntedMap {
-                    nodes: BTreeMap::from([
-                        (
-                            String::from("AAA"),
-                            HauntedNode::from_str("AAA = (BBB, CCC)").unwrap()
-                        ),
-                        (
-                            String::from("BBB"),
-                            HauntedNode::from_str("BBB = (DDD, EEE)").unwrap()
-                        ),
-                        (
-                            String::from("CCC"),
-                            HauntedNode::from_str("CCC = (ZZZ, GGG)").unwrap()
-                        ),
-                        (
-                            String::from("DDD"),
-                            HauntedNode::from_str("DDD = (DDD, DDD)").unwrap()
-                        ),
-                        (
-                            String::from("EEE"),
-                            HauntedNode::from_str("EEE = (EEE, EEE)").unwrap()
-                        ),
-                        (
-                            String::from("GGG"),
-                            HauntedNode::from_str("GGG = (GGG, GGG)").unwrap()
-                        ),
-                        (
-                            String::from("ZZZ"),
-                            HauntedNode::from_str("ZZZ = (ZZZ, ZZZ)").unwrap()
-                        ),
-                    ])
+                    // ids are numbered in the order they're first seen
+                    names: ["AAA", "BBB", "CCC", "DDD", "EEE", "ZZZ", "GGG"]
+                        .map(String::from)
+                        .to_vec(),
+                    indexes: BTreeMap::from([
+                        (String::from("AAA"), 0),
+                        (String::from("BBB"), 1),
+                        (String::from("CCC"), 2),
+                        (String::from("DDD"), 3),
+                        (String::from("EEE"), 4),
+                        (String::from("GGG"), 6),
+                        (String::from("ZZZ"), 5),
+                    ]),
+                    transitions: vec![
+                        Some([1, 2]),
+                        Some([3, 4]),
+                        Some([5, 6]),
+                        Some([3, 3]),
+                        Some([4, 4]),
+                        Some([5, 5]),
+                        Some([6, 6]),
+                    ],
                 }
             })
         );
+
+        // directions can only turn left or right
+        assert!(HauntedWasteland::from_str("LRX\n\nAAA = (AAA, AAA)").is_err());
     }
 
     #[test]
-    fn test_haunted_wasteland_turns() {
+    fn test_haunted_wasteland_walk() {
         let haunted_wasteland = HauntedWasteland::from_str(
             "RL
 
@@ -339,7 +653,13 @@ ZZZ = (ZZZ, ZZZ)",
         )
         .unwrap();
 
-        assert_eq!(haunted_wasteland.turns("AAA", "ZZZ"), vec!['R', 'L']);
+        assert_eq!(
+            haunted_wasteland.walk_to("AAA", &["ZZZ"], true),
+            Ok(Walk {
+                steps: 2,
+                path: Some(["AAA", "CCC", "ZZZ"].map(String::from).to_vec()),
+            })
+        );
 
         let haunted_wasteland = HauntedWasteland::from_str(
             "LLR
@@ -351,8 +671,56 @@ ZZZ = (ZZZ, ZZZ)",
         .unwrap();
 
         assert_eq!(
-            haunted_wasteland.turns("AAA", "ZZZ"),
-            vec!['L', 'L', 'R', 'L', 'L', 'R',]
+            haunted_wasteland.walk_to("AAA", &["ZZZ"], false),
+            Ok(Walk {
+                steps: 6,
+                path: None
+            })
+        );
+        assert_eq!(
+            haunted_wasteland
+                .walk("AAA", |node| node.starts_with('Z'), false)
+                .map(|walk| walk.steps),
+            Ok(6)
+        );
+        // the first target reached finishes the walk
+        assert_eq!(
+            haunted_wasteland
+                .walk_to("AAA", &["ZZZ", "BBB"], false)
+                .map(|walk| walk.steps),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn test_haunted_wasteland_walk_errors() {
+        let haunted_wasteland = HauntedWasteland::from_str(
+            "LR
+
+AAA = (BBB, AAA)
+BBB = (AAA, AAA)
+CCC = (DDD, DDD)
+ZZZ = (ZZZ, ZZZ)",
+        )
+        .unwrap();
+
+        assert_eq!(
+            haunted_wasteland.walk_to("AAA", &["ZZZ"], false),
+            Err(HauntedError::RepeatedState(String::from("AAA")))
+        );
+        assert_eq!(
+            haunted_wasteland.walk_to("XXX", &["ZZZ"], false),
+            Err(HauntedError::MissingNode(String::from("XXX")))
+        );
+        assert_eq!(
+            haunted_wasteland.walk_to("CCC", &["ZZZ"], false),
+            Err(HauntedError::MissingNode(String::from("DDD")))
+        );
+
+        let haunted_wasteland = HauntedWasteland::from_str("\n\nAAA = (ZZZ, ZZZ)").unwrap();
+        assert_eq!(
+            haunted_wasteland.walk_to("AAA", &["ZZZ"], false),
+            Err(HauntedError::NoDirections)
         );
     }
 
@@ -372,14 +740,101 @@ XXX = (XXX, XXX)",
         )
         .unwrap();
 
-        let turns = haunted_wasteland
-            .map
-            .nodes
-            .keys()
-            .filter(|key| key.ends_with('A'))
-            .map(|node| haunted_wasteland.turns_ends_with(node, 'Z').len())
-            .fold(1, lcm);
+        assert_eq!(
+            haunted_wasteland.cycle("11A", |index| haunted_wasteland.map.names[index] == "11Z"),
+            Ok(GhostCycle {
+                tail: 1,
+                length: 2,
+                tail_hits: vec![],
+                cycle_hits: vec![2],
+            })
+        );
+        assert_eq!(
+            haunted_wasteland.cycle("22A", |index| haunted_wasteland.map.names[index] == "22Z"),
+            Ok(GhostCycle {
+                tail: 1,
+                length: 6,
+                tail_hits: vec![],
+                cycle_hits: vec![3, 6],
+            })
+        );
+        assert_eq!(haunted_wasteland.ghost_steps('A', 'Z'), Ok(6));
+    }
+
+    #[test]
+    fn test_ghost_steps() {
+        // the first ghost hits on every odd step from 3, and the second on every
+        // sixth step from 5, so they first line up at 5 rather than lcm(3, 5)
+        let haunted_wasteland = HauntedWasteland::from_str(
+            "L
+
+11A = (11B, 11B)
+11B = (11C, 11C)
+11C = (11Z, 11Z)
+11Z = (11C, 11C)
+22A = (22B, 22B)
+22B = (22C, 22C)
+22C = (22D, 22D)
+22D = (22E, 22E)
+22E = (22Z, 22Z)
+22Z = (22A, 22A)",
+        )
+        .unwrap();
+        assert_eq!(haunted_wasteland.ghost_steps('A', 'Z'), Ok(5));
 
-        assert_eq!(turns, 6);
+        // a ghost that only hits a target on its way to the loop
+        let haunted_wasteland = HauntedWasteland::from_str(
+            "L
+
+11A = (11Z, 11Z)
+11Z = (11B, 11B)
+11B = (11B, 11B)
+22A = (22Z, 22Z)
+22Z = (22Z, 22Z)",
+        )
+        .unwrap();
+        assert_eq!(haunted_wasteland.ghost_steps('A', 'Z'), Ok(1));
+
+        // odd steps and even steps never line up
+        let haunted_wasteland = HauntedWasteland::from_str(
+            "L
+
+11A = (11Z, 11Z)
+11Z = (11A, 11A)
+22A = (22B, 22B)
+22B = (22Z, 22Z)
+22Z = (22C, 22C)
+22C = (22A, 22A)",
+        )
+        .unwrap();
+        assert_eq!(
+            haunted_wasteland.ghost_steps('A', 'Z'),
+            Err(HauntedError::NoSolution)
+        );
+
+        // a ghost that never reaches a target
+        let haunted_wasteland =
+            HauntedWasteland::from_str("L\n\n11A = (11Z, 11Z)\n11Z = (11Z, 11Z)\n22A = (22A, 22A)")
+                .unwrap();
+        assert_eq!(
+            haunted_wasteland.ghost_steps('A', 'Z'),
+            Err(HauntedError::RepeatedState(String::from("22A")))
+        );
+
+        // a ghost that steps off the map
+        let haunted_wasteland = HauntedWasteland::from_str("L\n\n11A = (11B, 11B)").unwrap();
+        assert_eq!(
+            haunted_wasteland.ghost_steps('A', 'Z'),
+            Err(HauntedError::MissingNode(String::from("11B")))
+        );
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt((2, 3), (3, 5)), Ok(Some((8, 15))));
+        assert_eq!(crt((1, 4), (3, 6)), Ok(Some((9, 12))));
+        assert_eq!(crt((1, 4), (2, 6)), Ok(None));
+        assert_eq!(crt((0, 1), (3, 7)), Ok(Some((3, 7))));
+        assert_eq!(crt((0, u128::MAX), (0, 2)), Err(HauntedError::Overflow));
     }
 }